@@ -0,0 +1,314 @@
+use crate::label::Label;
+use crate::lifecycle::refresh_lifecycle;
+use crate::menu::AliasReview;
+use crate::prompts::render_config;
+use crate::schema::Alias;
+use crate::sql::Sql;
+use crate::utils::print_table;
+use inquire::Select;
+use rusqlite::{params, Connection};
+
+// New codes whose label is at least this close to an existing one are flagged for review.
+// Models get a stricter bar: "Gol 1.0 8V" and "Gol 1.0 16V" are different vehicles.
+const BRAND_FUZZY_THRESHOLD: f64 = 0.9;
+const MODEL_FUZZY_THRESHOLD: f64 = 0.95;
+
+fn strip_accent(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+        'é' | 'è' | 'ê' | 'ë' => 'e',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+        'ú' | 'ù' | 'û' | 'ü' => 'u',
+        'ç' => 'c',
+        'ñ' => 'n',
+        _ => c,
+    }
+}
+
+// "GM - Chevrolet" -> "gm chevrolet"; casing, accents, punctuation and spacing are ignored.
+pub fn normalize_name(label: &str) -> String {
+    label
+        .to_lowercase()
+        .chars()
+        .map(strip_accent)
+        .map(|c| {
+            if c.is_alphanumeric() || c == '.' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// Levenshtein distance scaled to 0..1 over the normalised names.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = normalize_name(a).chars().collect();
+    let b: Vec<char> = normalize_name(b).chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+pub fn alias_checkpoint(conn: &Connection) -> Result<i64, Box<dyn std::error::Error>> {
+    Ok(
+        conn.query_row(Sql::SelectAliasCheckpoint.get().as_str(), [], |row| {
+            row.get(0)
+        })?,
+    )
+}
+
+// Same FIPE code, different label: record the label as an alias of the descriptor.
+// Cosmetic differences (casing, spacing, accents) are approved straight away.
+pub fn track_label(
+    conn: &Connection,
+    kind: &str,
+    descriptor_id: i32,
+    description: &str,
+    label: &str,
+    ref_id: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if description == label {
+        return Ok(());
+    }
+    let status = if normalize_name(description) == normalize_name(label) {
+        "approved"
+    } else {
+        "pending"
+    };
+    conn.prepare_cached(Sql::InsertAlias.get().as_str())?
+        .execute(params![
+            kind,
+            descriptor_id,
+            descriptor_id,
+            label,
+            "code",
+            similarity(description, label),
+            status,
+            ref_id
+        ])?;
+    Ok(())
+}
+
+// New FIPE codes whose label closely matches a descriptor that is not listed in the
+// same reference are probably the same brand / model under a new code.
+pub fn detect_fuzzy_aliases(
+    conn: &Connection,
+    kind: &str,
+    created: &[(i32, String)],
+    ref_id: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (sql, threshold) = if kind == "brand" {
+        (Sql::SelectBrandCandidates, BRAND_FUZZY_THRESHOLD)
+    } else {
+        (Sql::SelectModelCandidates, MODEL_FUZZY_THRESHOLD)
+    };
+    let mut stmt = conn.prepare(sql.get().as_str())?;
+    for (id, label) in created {
+        let candidates = stmt
+            .query_map(params![id, ref_id], |row| {
+                Ok((
+                    row.get::<_, i32>("id")?,
+                    row.get::<_, String>("description")?,
+                ))
+            })?
+            .collect::<Result<Vec<(i32, String)>, rusqlite::Error>>()?;
+        for (candidate_id, description) in candidates {
+            let score = similarity(&description, label);
+            if score >= threshold {
+                conn.prepare_cached(Sql::InsertAlias.get().as_str())?
+                    .execute(params![
+                        kind,
+                        id,
+                        candidate_id,
+                        label,
+                        "fuzzy",
+                        score,
+                        "pending",
+                        ref_id
+                    ])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn select_aliases(
+    conn: &Connection,
+    sql: Sql,
+    since: i64,
+) -> Result<Vec<Alias>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(sql.get().as_str())?;
+    let alias_iter = stmt.query_map([since], |row| {
+        Ok(Alias {
+            id: row.get("id")?,
+            kind: row.get("kind")?,
+            entity_id: row.get("entity_id")?,
+            canonical_id: row.get("canonical_id")?,
+            canonical: row.get("canonical")?,
+            label: row.get("label")?,
+            match_kind: row.get("match")?,
+            score: row.get("score")?,
+            status: row.get("status")?,
+            ref_date: row.get("ref_date")?,
+        })
+    })?;
+
+    let mut aliases = Vec::new();
+    for alias in alias_iter {
+        aliases.push(alias?);
+    }
+    Ok(aliases)
+}
+
+fn alias_rows(aliases: &[Alias]) -> Vec<Vec<String>> {
+    aliases
+        .iter()
+        .map(|a| {
+            vec![
+                a.kind.clone(),
+                a.canonical.clone(),
+                a.label.clone(),
+                a.match_kind.clone(),
+                format!("{:.2}", a.score),
+                a.status.clone(),
+                a.ref_date.clone().unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
+
+const ALIAS_HEADERS: [&str; 7] = [
+    "Kind",
+    "Canonical",
+    "Variant",
+    "Match",
+    "Score",
+    "Status",
+    "Reference",
+];
+
+// Variants detected since the checkpoint taken before a load.
+pub fn report_new_aliases(conn: &Connection, since: i64) -> Result<(), Box<dyn std::error::Error>> {
+    let aliases = select_aliases(conn, Sql::SelectAliasesSince, since)?;
+    if aliases.is_empty() {
+        return Ok(());
+    }
+    (Label::NewVariants {
+        count: &aliases.len().to_string(),
+    })
+    .log();
+    print_table(&ALIAS_HEADERS, &alias_rows(&aliases));
+    Ok(())
+}
+
+pub fn review_aliases(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let aliases = select_aliases(conn, Sql::SelectPendingAliases, 0)?;
+    if aliases.is_empty() {
+        Label::NoPendingAliases.log();
+        return Ok(());
+    }
+
+    for alias in &aliases {
+        print_table(&ALIAS_HEADERS, &alias_rows(std::slice::from_ref(alias)));
+        let options = vec![
+            AliasReview::Approve,
+            AliasReview::MakeCanonical,
+            AliasReview::Reject,
+            AliasReview::Skip,
+            AliasReview::Stop,
+        ];
+        let ans = Select::new("Alias", options)
+            .with_render_config(render_config())
+            .prompt()?;
+
+        match ans {
+            AliasReview::Approve => {
+                // A fuzzy match ties the new code to the existing descriptor.
+                if alias.entity_id != alias.canonical_id {
+                    conn.execute(
+                        (Sql::UpdateCanonical {
+                            kind: alias.kind.clone(),
+                        })
+                        .get()
+                        .as_str(),
+                        params![alias.canonical_id, alias.entity_id],
+                    )?;
+                }
+                conn.execute(
+                    Sql::UpdateAliasStatus.get().as_str(),
+                    params!["approved", alias.id],
+                )?;
+            }
+            AliasReview::MakeCanonical => {
+                conn.execute(
+                    (Sql::UpdateDescriptorName {
+                        kind: alias.kind.clone(),
+                    })
+                    .get()
+                    .as_str(),
+                    params![alias.label, alias.canonical_id],
+                )?;
+                if alias.entity_id != alias.canonical_id {
+                    conn.execute(
+                        (Sql::UpdateCanonical {
+                            kind: alias.kind.clone(),
+                        })
+                        .get()
+                        .as_str(),
+                        params![alias.canonical_id, alias.entity_id],
+                    )?;
+                }
+                // The previous name stays resolvable as an approved alias.
+                conn.execute(
+                    Sql::InsertAlias.get().as_str(),
+                    params![
+                        alias.kind,
+                        alias.canonical_id,
+                        alias.canonical_id,
+                        alias.canonical,
+                        "code",
+                        alias.score,
+                        "approved",
+                        Option::<i32>::None
+                    ],
+                )?;
+                conn.execute(
+                    Sql::UpdateAliasStatus.get().as_str(),
+                    params!["approved", alias.id],
+                )?;
+            }
+            AliasReview::Reject => {
+                conn.execute(
+                    Sql::UpdateAliasStatus.get().as_str(),
+                    params!["rejected", alias.id],
+                )?;
+            }
+            AliasReview::Skip => {}
+            AliasReview::Stop => {
+                break;
+            }
+        }
+    }
+    // Approved aliases share their canonical model's first / last seen.
+    refresh_lifecycle(conn)?;
+    Ok(())
+}
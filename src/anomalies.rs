@@ -0,0 +1,277 @@
+use crate::export::{write_csv, write_json};
+use crate::label::Label;
+use crate::menu::OutputFormat;
+use crate::prompts::prompt_output;
+use crate::schema::{Anomaly, AnomalyReport, AnomalySummary, PriceRelative};
+use crate::sql::Sql;
+use crate::utils::{format_model_year, format_pct, format_price, pct_change, print_table};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+
+// A month-over-month move is only flagged when it is at least this far (as a ratio) from
+// the model's typical move that month...
+const JUMP_RATIO: f64 = 1.5;
+// ...and, for models with enough vehicles to tell, a robust z-score outlier.
+const OUTLIER_SCORE: f64 = 3.5;
+// Below this many vehicles a model has no typical move and "no change" is the baseline.
+const MIN_PEERS: usize = 3;
+
+// Only the first rows are printed; CSV / JSON carry every row.
+const TERMINAL_LIMIT: usize = 30;
+
+// Flagged vehicle reference, detail and price ratio.
+type Flag = (i32, String, f64);
+
+const ANOMALY_HEADERS: [&str; 8] = [
+    "Reference",
+    "Kind",
+    "Type",
+    "Brand",
+    "Model",
+    "Year",
+    "FIPE Code",
+    "Detail",
+];
+
+fn select_price_relatives(
+    conn: &Connection,
+) -> Result<Vec<PriceRelative>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectPriceRelatives.get().as_str())?;
+    let relative_iter = stmt.query_map([], |row| {
+        Ok(PriceRelative {
+            id: row.get("id")?,
+            model_id: row.get("model_id")?,
+            ref_month: row.get("ref_month")?,
+            old_price: row.get("old_price")?,
+            new_price: row.get("new_price")?,
+        })
+    })?;
+
+    let mut relatives = Vec::new();
+    for relative in relative_iter {
+        relatives.push(relative?);
+    }
+    Ok(relatives)
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+// Vehicles whose price moved far more than the rest of their model in the same month.
+// Moves are compared as log relatives against the model's median, scaled by the median
+// absolute deviation (modified z-score).
+fn find_jumps(relatives: &[PriceRelative]) -> Vec<Flag> {
+    let mut groups: BTreeMap<(i32, &str), Vec<&PriceRelative>> = BTreeMap::new();
+    for r in relatives {
+        groups
+            .entry((r.model_id, r.ref_month.as_str()))
+            .or_default()
+            .push(r);
+    }
+
+    let mut jumps = Vec::new();
+    for peers in groups.values() {
+        let mut logs: Vec<f64> = peers
+            .iter()
+            .map(|r| (r.new_price / r.old_price).ln())
+            .collect();
+        let (baseline, mad) = if logs.len() >= MIN_PEERS {
+            let baseline = median(&mut logs);
+            let mut deviations: Vec<f64> = logs.iter().map(|l| (l - baseline).abs()).collect();
+            (baseline, median(&mut deviations))
+        } else {
+            (0.0, 0.0)
+        };
+
+        for r in peers {
+            let deviation = ((r.new_price / r.old_price).ln() - baseline).abs();
+            if deviation <= JUMP_RATIO.ln()
+                || (mad > 0.0 && 0.6745 * deviation / mad <= OUTLIER_SCORE)
+            {
+                continue;
+            }
+            let detail = format!(
+                "{} -> {} ({}; model {})",
+                format_price(r.old_price),
+                format_price(r.new_price),
+                format_pct(pct_change(Some(r.old_price), r.new_price)),
+                format_pct(Some((baseline.exp() - 1.0) * 100.0)),
+            );
+            jumps.push((r.id, detail, r.new_price / r.old_price));
+        }
+    }
+    jumps
+}
+
+fn find_year_inversions(conn: &Connection) -> Result<Vec<Flag>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectYearInversions.get().as_str())?;
+    let inversions = stmt
+        .query_map([], |row| {
+            let (year, older_year): (i32, i32) = (row.get("model_year")?, row.get("older_year")?);
+            let (price, older_price): (f64, f64) = (row.get("price")?, row.get("older_price")?);
+            Ok((
+                row.get("id")?,
+                format!(
+                    "{} at {} is cheaper than {} at {}",
+                    format_model_year(year),
+                    format_price(price),
+                    format_model_year(older_year),
+                    format_price(older_price)
+                ),
+                price / older_price,
+            ))
+        })?
+        .collect::<Result<Vec<Flag>, rusqlite::Error>>()?;
+    Ok(inversions)
+}
+
+// Re-runs both checks over every loaded price and replaces the `anomalies` table.
+// Returns the number of jumps and of model year inversions flagged.
+pub fn detect_anomalies(conn: &Connection) -> Result<(usize, usize), Box<dyn std::error::Error>> {
+    let jumps = find_jumps(&select_price_relatives(conn)?);
+    let inversions = find_year_inversions(conn)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(Sql::DeleteAnomalies.get().as_str(), [])?;
+    {
+        let mut stmt = tx.prepare(Sql::InsertAnomaly.get().as_str())?;
+        for (kind, rows) in [("jump", &jumps), ("year_inversion", &inversions)] {
+            for (id, detail, ratio) in rows {
+                stmt.execute(params![id, kind, detail, ratio])?;
+            }
+        }
+    }
+    tx.commit()?;
+    Ok((jumps.len(), inversions.len()))
+}
+
+// Data-quality pass run after each price load.
+pub fn check_anomalies(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    match detect_anomalies(conn)? {
+        (0, 0) => Label::NoAnomalies.log(),
+        (jumps, inversions) => (Label::AnomaliesFound {
+            jumps: &jumps.to_string(),
+            inversions: &inversions.to_string(),
+        })
+        .log(),
+    }
+    Ok(())
+}
+
+pub fn select_anomalies(conn: &Connection) -> Result<Vec<Anomaly>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectAnomalies.get().as_str())?;
+    let anomaly_iter = stmt.query_map([], |row| {
+        let ref_date: String = row.get("ref_date")?;
+        Ok(Anomaly {
+            reference: ref_date.chars().take(7).collect(),
+            kind: row.get("kind")?,
+            type_description: row.get("type_description")?,
+            brand: row.get("brand_description")?,
+            model: row.get("model_description")?,
+            year: row.get("year_description")?,
+            fipe_code: row.get("fipe_code")?,
+            detail: row.get("detail")?,
+            ratio: row.get("ratio")?,
+        })
+    })?;
+
+    let mut anomalies = Vec::new();
+    for anomaly in anomaly_iter {
+        anomalies.push(anomaly?);
+    }
+    Ok(anomalies)
+}
+
+// Anomaly counts per reference and kind, newest reference first.
+pub fn anomaly_report(conn: &Connection) -> Result<AnomalyReport, Box<dyn std::error::Error>> {
+    let anomalies = select_anomalies(conn)?;
+    let mut counts: BTreeMap<(&str, &str), usize> = BTreeMap::new();
+    for a in &anomalies {
+        *counts
+            .entry((a.reference.as_str(), a.kind.as_str()))
+            .or_default() += 1;
+    }
+    let summary = counts
+        .into_iter()
+        .rev()
+        .map(|((reference, kind), count)| AnomalySummary {
+            reference: reference.to_string(),
+            kind: kind.to_string(),
+            count,
+        })
+        .collect();
+    Ok(AnomalyReport { summary, anomalies })
+}
+
+fn anomaly_row(a: &Anomaly) -> Vec<String> {
+    vec![
+        a.reference.clone(),
+        a.kind.clone(),
+        a.type_description.clone(),
+        a.brand.clone(),
+        a.model.clone(),
+        a.year.clone(),
+        a.fipe_code.clone().unwrap_or_default(),
+        a.detail.clone(),
+    ]
+}
+
+pub fn show_anomalies(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let output = prompt_output()?;
+    let report = anomaly_report(conn)?;
+    if report.anomalies.is_empty() {
+        Label::NoAnomalies.log();
+        return Ok(());
+    }
+
+    let summary: Vec<Vec<String>> = report
+        .summary
+        .iter()
+        .map(|s| vec![s.reference.clone(), s.kind.clone(), s.count.to_string()])
+        .collect();
+    match output {
+        OutputFormat::Terminal => {
+            print_table(&["Reference", "Kind", "Count"], &summary);
+            let rows: Vec<Vec<String>> = report
+                .anomalies
+                .iter()
+                .take(TERMINAL_LIMIT)
+                .map(anomaly_row)
+                .collect();
+            print_table(&ANOMALY_HEADERS, &rows);
+            if report.anomalies.len() > rows.len() {
+                (Label::TruncatedOutput {
+                    shown: &rows.len().to_string(),
+                    total: &report.anomalies.len().to_string(),
+                })
+                .log();
+            }
+        }
+        OutputFormat::Csv => {
+            let mut headers = ANOMALY_HEADERS.to_vec();
+            headers.push("Ratio");
+            let rows: Vec<Vec<String>> = report
+                .anomalies
+                .iter()
+                .map(|a| {
+                    let mut row = anomaly_row(a);
+                    row.push(format!("{:.4}", a.ratio));
+                    row
+                })
+                .collect();
+            write_csv("anomalies", &headers, &rows)?;
+            write_csv("anomaly_summary", &["Reference", "Kind", "Count"], &summary)?;
+        }
+        OutputFormat::Json => {
+            write_json("anomalies", &report)?;
+        }
+    }
+    Ok(())
+}
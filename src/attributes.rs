@@ -0,0 +1,225 @@
+use crate::label::Label;
+use crate::schema::ModelAttributes;
+use crate::sql::Sql;
+use crate::utils::progress_bar;
+use rusqlite::{params, Connection};
+
+// Splits "Civic Sedan EXL 2.0 Flex 16V Aut. 4p" into lowercase words without trailing dots.
+fn tokens(label: &str) -> Vec<String> {
+    label
+        .split(|c: char| c.is_whitespace() || c == '/' || c == ',' || c == '(' || c == ')')
+        .map(|t| t.trim_end_matches('.').to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+// "1.6" -> 1.6; motorcycle cc figures ("160") are left out on purpose.
+fn displacement(token: &str) -> Option<f64> {
+    let (whole, frac) = token.split_once('.')?;
+    let valid = whole.len() == 1
+        && (1..=2).contains(&frac.len())
+        && whole
+            .chars()
+            .chain(frac.chars())
+            .all(|c| c.is_ascii_digit());
+    if valid {
+        token.parse().ok()
+    } else {
+        None
+    }
+}
+
+// "16v" -> 16
+fn valves(token: &str) -> Option<i32> {
+    let number = token.strip_suffix('v')?;
+    match number.parse::<i32>() {
+        Ok(v) if [2, 4, 6, 8, 10, 12, 16, 20, 24, 32, 40, 48].contains(&v) => Some(v),
+        _ => None,
+    }
+}
+
+// "4p" / "4portas" -> 4
+fn doors(token: &str) -> Option<i32> {
+    let number = token
+        .strip_suffix("portas")
+        .or_else(|| token.strip_suffix('p'))?;
+    match number.parse::<i32>() {
+        Ok(d) if (2..=5).contains(&d) => Some(d),
+        _ => None,
+    }
+}
+
+fn transmission(token: &str) -> Option<&'static str> {
+    match token {
+        "aut" | "automático" | "automatico" | "tiptronic" | "s-tronic" | "dsg" | "steptronic"
+        | "multitronic" => Some("automatic"),
+        "cvt" | "x-tronic" | "xtronic" => Some("cvt"),
+        "automatizado" | "automatizada" | "i-motion" | "dualogic" | "easytronic" | "gsr" => {
+            Some("automated")
+        }
+        "mec" | "manual" => Some("manual"),
+        _ => None,
+    }
+}
+
+fn is_turbo(token: &str) -> bool {
+    matches!(
+        token,
+        "turbo"
+            | "tb"
+            | "tsi"
+            | "tfsi"
+            | "thp"
+            | "t-jet"
+            | "tjet"
+            | "ecoboost"
+            | "turbodiesel"
+            | "tdi"
+            | "biturbo"
+            | "t270"
+            | "tgdi"
+    )
+}
+
+fn fuel(token: &str) -> Option<&'static str> {
+    match token {
+        "flex" | "flexstart" | "flexpower" | "tetrafuel" => Some("flex"),
+        "gasolina" | "gas" => Some("gasolina"),
+        "diesel" | "dies" | "tdi" | "turbodiesel" => Some("diesel"),
+        "álcool" | "alcool" => Some("álcool"),
+        "gnv" => Some("gás natural"),
+        "elétrico" | "eletrico" | "electric" | "ev" => Some("elétrico"),
+        "híbrido" | "hibrido" | "hybrid" => Some("híbrido"),
+        "plug-in" | "phev" => Some("híbrido plug-in"),
+        _ => None,
+    }
+}
+
+fn body_style(token: &str) -> Option<&'static str> {
+    match token {
+        "sedan" | "sedã" => Some("sedan"),
+        "hatch" | "hatchback" => Some("hatch"),
+        "sw" | "wagon" | "variant" | "perua" | "avant" | "touring" => Some("wagon"),
+        "cd" => Some("pickup double cab"),
+        "cs" => Some("pickup single cab"),
+        "ce" => Some("pickup extended cab"),
+        "conv" | "cabrio" | "cabriolet" | "conversível" | "conversivel" | "roadster" => {
+            Some("convertible")
+        }
+        "coupe" | "coupé" | "cupê" | "cupe" => Some("coupe"),
+        "furgão" | "furgao" | "van" | "furgon" => Some("van"),
+        _ => None,
+    }
+}
+
+// Best-effort parse of a FIPE model label; anything not stated stays None.
+pub fn parse_model_attributes(label: &str) -> ModelAttributes {
+    let mut attributes = ModelAttributes::default();
+    let tokens = tokens(label);
+    for (i, token) in tokens.iter().enumerate() {
+        if attributes.engine_displacement.is_none() {
+            attributes.engine_displacement = displacement(token);
+        }
+        if attributes.valves.is_none() {
+            attributes.valves = valves(token);
+        }
+        if attributes.doors.is_none() {
+            attributes.doors = doors(token);
+        }
+        if attributes.transmission.is_none() {
+            attributes.transmission = transmission(token).map(str::to_string);
+        }
+        if attributes.body_style.is_none() {
+            attributes.body_style = body_style(token).map(str::to_string);
+        }
+        // "Plug-in" outranks a plain "Hybrid" wherever it appears.
+        if token == "plug-in" || (token == "plug" && tokens.get(i + 1).is_some_and(|t| t == "in")) {
+            attributes.fuel = Some("híbrido plug-in".to_string());
+        } else if attributes.fuel.is_none() {
+            attributes.fuel = fuel(token).map(str::to_string);
+        }
+        attributes.turbo |= is_turbo(token);
+    }
+    attributes
+}
+
+// Re-parses every stored model label, for rows loaded before parsing existed.
+pub fn backfill_attributes(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectModelDescriptions.get().as_str())?;
+    let models = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i32>("id")?,
+                row.get::<_, String>("description")?,
+            ))
+        })?
+        .collect::<Result<Vec<(i32, String)>, rusqlite::Error>>()?;
+
+    let pb = progress_bar(models.len().try_into().unwrap_or(0));
+    let tx = conn.unchecked_transaction()?;
+    {
+        let mut update = tx.prepare(Sql::UpdateModelAttributes.get().as_str())?;
+        for (id, description) in &models {
+            let a = parse_model_attributes(description);
+            update.execute(params![
+                a.engine_displacement,
+                a.valves,
+                a.doors,
+                a.transmission,
+                a.turbo,
+                a.body_style,
+                a.fuel,
+                id
+            ])?;
+            pb.inc(1);
+        }
+    }
+    tx.commit()?;
+    pb.finish_with_message(
+        (Label::LoadOk {
+            entity: "Model attributes",
+        })
+        .to_string(),
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_car_label() {
+        let a = parse_model_attributes("Civic Sedan EXL 2.0 Flex 16V Aut. 4p");
+        assert_eq!(a.engine_displacement, Some(2.0));
+        assert_eq!(a.valves, Some(16));
+        assert_eq!(a.doors, Some(4));
+        assert_eq!(a.transmission.as_deref(), Some("automatic"));
+        assert_eq!(a.body_style.as_deref(), Some("sedan"));
+        assert_eq!(a.fuel.as_deref(), Some("flex"));
+        assert!(!a.turbo);
+    }
+
+    #[test]
+    fn parses_turbo_and_plug_in() {
+        let a = parse_model_attributes("XC60 T8 Recharge Hybrid Plug-in 2.0 TB Aut.");
+        assert!(a.turbo);
+        assert_eq!(a.fuel.as_deref(), Some("híbrido plug-in"));
+
+        let a = parse_model_attributes("Golf GTE Hybrid Plug in 1.4 TSI");
+        assert!(a.turbo);
+        assert_eq!(a.fuel.as_deref(), Some("híbrido plug-in"));
+    }
+
+    #[test]
+    fn leaves_unstated_attributes_empty() {
+        let a = parse_model_attributes("CG 160 Titan");
+        assert_eq!(a.engine_displacement, None);
+        assert_eq!(a.valves, None);
+        assert_eq!(a.doors, None);
+        assert_eq!(a.transmission, None);
+        assert_eq!(a.body_style, None);
+        assert_eq!(a.fuel, None);
+        assert!(!a.turbo);
+    }
+}
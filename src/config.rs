@@ -1,72 +1,167 @@
 use crate::label::Label;
-use crate::schema::{Count, RowCount, Status};
-use crate::sql::Sql;
-use crate::utils::{clear_screen, progress_bar};
+use crate::schema::{Count, HealthIssue, HealthRow, LoadEstimates, Status};
+use crate::selects::{select_all_references, select_references};
+use crate::sql::{Sql, SCHEMA_VERSION};
+use crate::utils::{print_table, progress_bar};
+use crate::webhooks::Event;
 
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Result};
 
+// Only the first issues are printed; the summary counts cover everything.
+const TERMINAL_LIMIT: usize = 20;
+
+fn select_health_summary(conn: &Connection) -> Result<Vec<HealthRow>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectHealthSummary.get().as_str())?;
+    let rows = stmt.query_map([], |row| {
+        Ok(HealthRow {
+            ref_date: row.get("ref_date")?,
+            type_description: row.get("type_description")?,
+            brands: row.get("brands")?,
+            brands_with_models: row.get("brands_with_models")?,
+            models: row.get("models")?,
+            models_with_years: row.get("models_with_years")?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<HealthRow>, rusqlite::Error>>()?)
+}
+
+fn select_health_issues(conn: &Connection) -> Result<Vec<HealthIssue>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectHealthIssues.get().as_str())?;
+    let rows = stmt.query_map([], |row| {
+        Ok(HealthIssue {
+            issue: row.get("issue")?,
+            ref_date: row.get("ref_date")?,
+            type_description: row.get("type_description")?,
+            brand: row.get("brand")?,
+            model: row.get("model")?,
+            year: row.get("year")?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<HealthIssue>, rusqlite::Error>>()?)
+}
+
+fn health_state(row: &HealthRow) -> &'static str {
+    if row.brands == 0 {
+        "missing"
+    } else if row.brands_with_models < row.brands || row.models_with_years < row.models {
+        "partial"
+    } else {
+        "complete"
+    }
+}
+
+// Brands per reference × type, models per brand and years per model, plus orphans.
+// The database is "updated" only when every reference is fully loaded, nothing is orphaned
+// and the newest FIPE reference seen by Check for Updates is loaded.
 pub fn check_db(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
-    clear_screen();
-    let m = MultiProgress::new();
-    let style = ProgressStyle::with_template(
-        "{msg}{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
-    )
-    .unwrap()
-    .progress_chars("=> ");
-    let counts = match select_rowcount(conn) {
-        Ok(counts) => counts,
-        Err(err) => {
-            panic!("lmao {}", err)
+    let summary = select_health_summary(conn)?;
+    if summary.is_empty() {
+        Label::NoReferences.log();
+        update_status(conn, "empty")?;
+        return Ok(());
+    }
+    let issues = select_health_issues(conn)?;
+
+    let incomplete: Vec<Vec<String>> = summary
+        .iter()
+        .filter(|r| health_state(r) != "complete")
+        .map(|r| {
+            vec![
+                r.ref_date.clone(),
+                r.type_description.clone(),
+                health_state(r).to_string(),
+                r.brands.to_string(),
+                format!("{}/{}", r.brands_with_models, r.brands),
+                format!("{}/{}", r.models_with_years, r.models),
+            ]
+        })
+        .collect();
+    (Label::HealthTitle {
+        complete: &(summary.len() - incomplete.len()).to_string(),
+        total: &summary.len().to_string(),
+    })
+    .log();
+    if !incomplete.is_empty() {
+        print_table(
+            &[
+                "Reference",
+                "Type",
+                "State",
+                "Brands",
+                "With Models",
+                "With Years",
+            ],
+            &incomplete,
+        );
+        println!();
+    }
+
+    if issues.is_empty() {
+        Label::NoIssues.log();
+    } else {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for i in &issues {
+            match counts.iter_mut().find(|(issue, _)| *issue == i.issue) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((i.issue.clone(), 1)),
+            }
         }
-    };
+        let rows: Vec<Vec<String>> = counts
+            .into_iter()
+            .map(|(issue, n)| vec![issue, n.to_string()])
+            .collect();
+        print_table(&["Issue", "Count"], &rows);
+        println!();
 
-    // Definição das barras
-    let pb_years = m.add(ProgressBar::new(
-        counts.years_rowcount.try_into().unwrap_or(0),
-    ));
-    pb_years.set_style(style.clone());
-    pb_years.set_message("References");
-
-    let pb_brands = m.add(ProgressBar::new(
-        counts.brands_rowcount.try_into().unwrap_or(0),
-    ));
-    pb_brands.set_style(style.clone());
-    pb_brands.set_message("Brands");
-
-    let pb_models = m.add(ProgressBar::new(
-        counts.models_rowcount.try_into().unwrap_or(0),
-    ));
-    pb_models.set_style(style.clone());
-    pb_models.set_message("Models");
-
-    // Persistência: Impede que as barras sejam limpas ao finalizar
-    pb_years.finish_with_message("Years");
-    pb_brands.finish_with_message("Brands");
-    pb_models.finish_with_message("Models");
-
-    // Contagem atual
-    let years_count: u64 = match select_count(conn, "years") {
-        Ok(count) => count.count.try_into().unwrap_or(0),
-        Err(_) => 0,
-    };
-    let brands_count: u64 = match select_count(conn, "brands") {
-        Ok(count) => count.count.try_into().unwrap_or(0),
-        Err(_) => 0,
+        let rows: Vec<Vec<String>> = issues
+            .iter()
+            .take(TERMINAL_LIMIT)
+            .map(|i| {
+                vec![
+                    i.issue.clone(),
+                    i.ref_date.clone().unwrap_or_default(),
+                    i.type_description.clone().unwrap_or_default(),
+                    i.brand.clone().unwrap_or_default(),
+                    i.model.clone().unwrap_or_default(),
+                    i.year.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        print_table(
+            &["Issue", "Reference", "Type", "Brand", "Model", "Year"],
+            &rows,
+        );
+        if issues.len() > rows.len() {
+            (Label::TruncatedOutput {
+                shown: &rows.len().to_string(),
+                total: &issues.len().to_string(),
+            })
+            .log();
+        }
+    }
+
+    let latest_loaded = match select_status(conn)?
+        .latest_remote_reference
+        .as_deref()
+        .and_then(remote_reference_code)
+    {
+        Some(codigo) => reference_loaded(conn, codigo)?,
+        None => false,
     };
-    let models_count: u64 = match select_count(conn, "models") {
-        Ok(count) => count.count.try_into().unwrap_or(0),
-        Err(_) => 0,
+    // Descriptors no reference lists any more (left behind by the delete actions) are shown
+    // for information only; they don't make the database outdated.
+    let blocking = issues.iter().any(|i| !i.issue.starts_with("unreferenced"));
+    let status = if incomplete.is_empty() && !blocking && latest_loaded {
+        "updated"
+    } else {
+        "outdated"
     };
-
-    // Simulação de atualização baseada nos seus counts
-    pb_years.set_position(years_count);
-    pb_brands.set_position(brands_count);
-    pb_models.set_position(models_count);
+    update_status(conn, status)?;
     Ok(())
 }
+
 pub fn setup_db(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
-    let pb = progress_bar(11);
+    let pb = progress_bar(24);
     // Drops
     conn.execute_batch(Sql::DropTables.get().as_str())?;
     pb.inc(1);
@@ -143,32 +238,119 @@ pub fn setup_db(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     );
     conn.execute_batch(Sql::CreateModels.get().as_str())?;
     pb.inc(1);
-    // Years
+    // Vehicles
     pb.set_message(
         (Label::CreateTable {
-            table_name: "years",
+            table_name: "vehicles",
         })
         .to_string(),
     );
-    conn.execute_batch(Sql::CreateYears.get().as_str())?;
+    conn.execute_batch(Sql::CreateVehicles.get().as_str())?;
+    pb.inc(1);
+    pb.set_message(
+        (Label::CreateTable {
+            table_name: "vehicle_references",
+        })
+        .to_string(),
+    );
+    conn.execute_batch(Sql::CreateVehicleReferences.get().as_str())?;
+    pb.inc(1);
+    // Search
+    pb.set_message(
+        (Label::CreateTable {
+            table_name: "search_index",
+        })
+        .to_string(),
+    );
+    conn.execute_batch(Sql::CreateSearchIndex.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateAliases.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateLifecycle.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateReloadLog.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateBrandYears.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreatePriceLookups.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateInflationIndex.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateFailedRequests.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateAnomalies.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateWatchlist.get().as_str())?;
+    pb.inc(1);
+    conn.execute_batch(Sql::CreateWebhooks.get().as_str())?;
+    pb.inc(1);
+    // Views
+    pb.set_message(Label::CreateViews.to_string());
+    conn.execute_batch(Sql::CreateViews.get().as_str())?;
     pb.inc(1);
     // Indexes
     pb.set_message(Label::CreateIndexes.to_string());
     conn.execute_batch(Sql::CreateIndexes.get().as_str())?;
+    conn.execute_batch(
+        (Sql::UpdateSchemaVersion {
+            version: SCHEMA_VERSION,
+        })
+        .get()
+        .as_str(),
+    )?;
     pb.inc(1);
 
     pb.finish_with_message(Label::DbCreationOk.to_string());
     Ok(())
 }
 
+pub fn migrate_db(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let version: i32 = conn.query_row(Sql::SelectSchemaVersion.get().as_str(), [], |row| {
+        row.get(0)
+    })?;
+    if version >= SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    // Databases that were never set up get the current schema from setup_db instead.
+    // "references" is a table at every schema version (brands, models and years became views).
+    let tables: i32 = conn.query_row(
+        Sql::SelectTableExists.get().as_str(),
+        ["references"],
+        |row| row.get(0),
+    )?;
+    if tables == 0 {
+        return Ok(());
+    }
+
+    for v in (version + 1)..=SCHEMA_VERSION {
+        let tx = conn.unchecked_transaction()?;
+        tx.execute_batch((Sql::Migrate { version: v }).get().as_str())?;
+        tx.execute_batch((Sql::UpdateSchemaVersion { version: v }).get().as_str())?;
+        tx.commit()?;
+        (Label::MigrationOk {
+            version: &v.to_string(),
+        })
+        .log();
+    }
+    Ok(())
+}
+
+// Off by default in SQLite; enabled per connection once migrations (which rebuild tables) are done.
+pub fn enable_foreign_keys(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute_batch(Sql::EnableForeignKeys.get().as_str())?;
+    Ok(())
+}
+
 pub fn select_status(conn: &Connection) -> Result<Status, Box<dyn std::error::Error>> {
-    let mut stmt = match conn.prepare(&Sql::SelectStatus.get().as_str()) {
+    let mut stmt = match conn.prepare(Sql::SelectStatus.get().as_str()) {
         Ok(s) => s,
         _ => {
-            conn.execute_batch(&Sql::CreateConfig.get().as_str())?;
+            conn.execute_batch(Sql::CreateConfig.get().as_str())?;
             return Ok(Status {
                 db_status: "empty".to_string(),
                 last_update: None,
+                latest_remote_reference: None,
             });
         }
     };
@@ -177,6 +359,7 @@ pub fn select_status(conn: &Connection) -> Result<Status, Box<dyn std::error::Er
         Ok(Status {
             db_status: row.get("db_status")?,
             last_update: row.get("last_update")?,
+            latest_remote_reference: row.get("latest_remote_reference")?,
         })
     })?;
 
@@ -189,64 +372,104 @@ pub fn select_status(conn: &Connection) -> Result<Status, Box<dyn std::error::Er
         Ok(Status {
             db_status: "empty".to_string(),
             last_update: None,
+            latest_remote_reference: None,
         })
     }
 }
 
-/*
-stable
-empty
-updated
-outdated
-*/
+// empty: nothing loaded
+// updated: newest FIPE reference loaded (and nothing partial, after a health check)
+// outdated: anything else
 pub fn update_status(conn: &Connection, status: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let previous: Option<String> = conn
+        .query_row(Sql::SelectStatus.get().as_str(), [], |row| {
+            row.get("db_status")
+        })
+        .optional()?;
     conn.execute(Sql::UpdateStatus.get().as_str(), params![status])?;
+    if previous.as_deref() != Some(status) {
+        (Event::StatusChanged {
+            from: previous.as_deref().unwrap_or("empty"),
+            to: status,
+        })
+        .emit(conn)?;
+    }
     Ok(())
 }
 
-pub fn select_rowcount(conn: &Connection) -> Result<RowCount, Box<dyn std::error::Error>> {
-    let create_config = Sql::CreateConfig.get();
-    let select_row_count = Sql::SelectRowCount.get();
-    let mut stmt = match conn.prepare(&select_row_count) {
-        Ok(s) => s,
-        _ => {
-            conn.execute_batch(&create_config)?;
-            return Ok(RowCount {
-                brands_rowcount: 0,
-                models_rowcount: 0,
-                years_rowcount: 0,
-                vehicles_rowcount: 0,
-            });
-        }
-    };
+pub fn update_remote_reference(
+    conn: &Connection,
+    reference: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(Sql::UpdateRemoteReference.get().as_str(), params![reference])?;
+    Ok(())
+}
 
-    let mut config_iter = stmt.query_map([], |row| {
-        Ok(RowCount {
-            brands_rowcount: row.get("brands_rowcount")?,
-            models_rowcount: row.get("models_rowcount")?,
-            years_rowcount: row.get("years_rowcount")?,
-            vehicles_rowcount: row.get("vehicles_rowcount")?,
-        })
-    })?;
+// "fevereiro/2024 (305)" -> "305", as stored by update_remote_reference.
+pub fn remote_reference_code(reference: &str) -> Option<&str> {
+    reference.rsplit_once('(')?.1.strip_suffix(')')
+}
 
-    if let Some(config) = config_iter.next() {
-        Ok(config?)
-    } else {
-        Ok(RowCount {
-            brands_rowcount: 0,
-            models_rowcount: 0,
-            years_rowcount: 0,
-            vehicles_rowcount: 0,
+// Listed locally is not enough: the reference's brands have to be loaded too.
+pub fn reference_loaded(
+    conn: &Connection,
+    codigo: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let listed = select_all_references(conn)?
+        .iter()
+        .any(|r| r.fipe.trim() == codigo);
+    let pending = select_references(conn)?
+        .iter()
+        .any(|r| r.fipe.trim() == codigo);
+    Ok(listed && !pending)
+}
+
+pub fn select_load_estimates(
+    conn: &Connection,
+) -> Result<LoadEstimates, Box<dyn std::error::Error>> {
+    let select_load_estimates = Sql::SelectLoadEstimates.get();
+    if conn.prepare(&select_load_estimates).is_err() {
+        conn.execute_batch(Sql::CreateConfig.get().as_str())?;
+    }
+    Ok(conn.query_row(&select_load_estimates, [], |row| {
+        Ok(LoadEstimates {
+            brands_per_parent: row.get("brands_per_parent")?,
+            models_per_parent: row.get("models_per_parent")?,
+            years_per_parent: row.get("years_per_parent")?,
         })
+    })?)
+}
+
+// Stores the rows per parent observed in the loaded data and returns the updated estimates.
+// Entities with nothing loaded yet keep their previous estimate.
+pub fn calibrate_load_estimates(
+    conn: &Connection,
+) -> Result<LoadEstimates, Box<dyn std::error::Error>> {
+    let observed: Vec<(&str, Option<f64>)> =
+        conn.query_row(Sql::SelectObservedEstimates.get().as_str(), [], |row| {
+            Ok(vec![
+                ("brands", row.get("brands_per_parent")?),
+                ("models", row.get("models_per_parent")?),
+                ("years", row.get("years_per_parent")?),
+            ])
+        })?;
+    for (entity, per_parent) in observed {
+        if let Some(per_parent) = per_parent {
+            conn.execute(
+                (Sql::UpdateLoadEstimate {
+                    entity: entity.to_string(),
+                })
+                .get()
+                .as_str(),
+                params![per_parent],
+            )?;
+        }
     }
+    select_load_estimates(conn)
 }
 
-pub fn select_count(conn: &Connection, entity: &str) -> Result<Count, Box<dyn std::error::Error>> {
-    let select_count = (Sql::SelectCount {
-        entity: entity.to_string(),
-    })
-    .get();
-    let mut stmt = match conn.prepare(&select_count) {
+pub fn select_count_sql(conn: &Connection, sql: Sql) -> Result<Count, Box<dyn std::error::Error>> {
+    let mut stmt = match conn.prepare(sql.get().as_str()) {
         Ok(s) => s,
         _ => {
             return Ok(Count { count: 0 });
@@ -264,3 +487,101 @@ pub fn select_count(conn: &Connection, entity: &str) -> Result<Count, Box<dyn st
         Ok(Count { count: 0 })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Schema as created before versioning, with one listed vehicle.
+    const V0_SCHEMA: &str = r#"
+        CREATE TABLE config(
+            db_status text,
+            last_update date,
+            brands_rowcount integer default 51500,
+            models_rowcount integer default 1970128,
+            years_rowcount integer default 8119581,
+            vehicles_rowcount integer default 0
+        );
+        INSERT INTO config(db_status, last_update) VALUES ('updated', datetime('now', 'localtime'));
+        CREATE TABLE "references"(id integer PRIMARY KEY, ref_date date, fipe text unique);
+        CREATE TABLE types(id integer PRIMARY KEY, description text);
+        CREATE TABLE fuels(id integer PRIMARY KEY, description text);
+        CREATE TABLE brands(
+            id integer PRIMARY KEY, description text, fipe text, type_id integer, ref_id integer,
+            unique(fipe, ref_id)
+        );
+        CREATE TABLE models(
+            id integer PRIMARY KEY, description text, fipe text, brand_id integer,
+            unique(fipe, brand_id)
+        );
+        CREATE TABLE years(
+            id integer PRIMARY KEY, description text, value date, fipe text, model_id integer,
+            fuel_id integer, unique(fipe, model_id)
+        );
+        INSERT INTO "references"(ref_date, fipe) VALUES ('2024-01-01', '300');
+        INSERT INTO types(description) VALUES ('cars'), ('motorcycles'), ('trucks');
+        INSERT INTO fuels(description) VALUES ('Gasolina');
+        INSERT INTO brands(description, fipe, type_id, ref_id) VALUES ('VW', '59', 1, 1);
+        INSERT INTO models(description, fipe, brand_id) VALUES ('Gol', '5940', 1);
+        INSERT INTO years(description, value, fipe, model_id, fuel_id)
+            VALUES ('2015 Gasolina', '2015-01-01', '2015-1', 1, 1);
+    "#;
+
+    #[test]
+    fn migrates_v3_database_to_current_schema() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(V0_SCHEMA).unwrap();
+        for v in 1..=3 {
+            conn.execute_batch((Sql::Migrate { version: v }).get().as_str())
+                .unwrap();
+            conn.execute_batch((Sql::UpdateSchemaVersion { version: v }).get().as_str())
+                .unwrap();
+        }
+
+        migrate_db(&conn).unwrap();
+
+        let version: i32 = conn
+            .query_row(Sql::SelectSchemaVersion.get().as_str(), [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+
+        // The seeded vehicle is still listed through the views and its reference membership.
+        let listed: (String, String, String, String) = conn
+            .query_row(
+                r#"
+                SELECT b.description, m.description, y.fipe, r.fipe
+                FROM years y
+                JOIN models m ON y.model_id = m.id
+                JOIN brands b ON m.brand_id = b.id
+                JOIN "references" r ON b.ref_id = r.id
+                "#,
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(
+            listed,
+            (
+                "VW".to_string(),
+                "Gol".to_string(),
+                "2015-1".to_string(),
+                "300".to_string()
+            )
+        );
+        let memberships: i32 = conn
+            .query_row(
+                r#"
+                SELECT COUNT(*)
+                FROM vehicle_references vr
+                JOIN vehicles v ON vr.vehicle_id = v.id
+                WHERE v.fipe = '2015-1' AND v.model_year = 2015
+                "#,
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(memberships, 1);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::label::Label;
+use crate::lifecycle::refresh_lifecycle;
+use crate::prompts::{prompt_choice, prompt_reference, prompt_type};
+use crate::selects::select_choices;
+use crate::sql::Sql;
+use inquire::Confirm;
+use rusqlite::{params, Connection};
+
+fn confirm_delete(what: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(
+        Confirm::new(&format!("Delete {} and everything loaded under it?", what))
+            .with_default(false)
+            .prompt()?,
+    )
+}
+
+// Removes the reference with its brands, models, years and prices; Load References brings it back.
+pub fn delete_reference(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(reference) = prompt_reference(conn, "Reference", 0)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    if !confirm_delete(&reference.description)? {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(Sql::DeleteReference.get().as_str(), params![reference.id])?;
+    refresh_lifecycle(&tx)?;
+    tx.commit()?;
+    (Label::DeleteOk {
+        what: &reference.description,
+    })
+    .log();
+    Ok(())
+}
+
+// Removes one brand's listing in a reference with its models, years and prices.
+pub fn delete_brand_reference(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(reference) = prompt_reference(conn, "Reference", 0)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let Some(tipo) = prompt_type(conn)? else {
+        return Ok(());
+    };
+    let brands = select_choices(
+        conn,
+        Sql::SelectReferenceBrandChoices,
+        [reference.id, tipo.id],
+    )?;
+    let Some(brand) = prompt_choice("Brand", brands)? else {
+        Label::NothingLoaded.log();
+        return Ok(());
+    };
+    let what = format!("{} ({})", brand.description, reference.description);
+    if !confirm_delete(&what)? {
+        return Ok(());
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(Sql::DeleteBrandReference.get().as_str(), params![brand.id])?;
+    refresh_lifecycle(&tx)?;
+    tx.commit()?;
+    (Label::DeleteOk { what: &what }).log();
+    Ok(())
+}
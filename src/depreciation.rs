@@ -0,0 +1,206 @@
+use crate::export::{write_csv, write_json};
+use crate::label::Label;
+use crate::menu::{DepreciationScope, OutputFormat};
+use crate::prompts::{prompt_brand, prompt_output, prompt_reference, prompt_type, render_config};
+use crate::schema::{DepreciationPoint, DepreciationPrice};
+use crate::sql::Sql;
+use crate::utils::{format_pct, format_price, pct_change, print_table};
+use inquire::{Confirm, Select};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+
+const DEPRECIATION_HEADERS: [&str; 6] = [
+    "Group",
+    "Age",
+    "Avg Price",
+    "Retention",
+    "Yearly Change",
+    "Samples",
+];
+
+pub fn select_depreciation_prices(
+    conn: &Connection,
+    ref_id: Option<i32>,
+    type_id: Option<i32>,
+    brand_id: Option<i32>,
+) -> Result<Vec<DepreciationPrice>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectDepreciationPrices.get().as_str())?;
+    let price_iter = stmt.query_map(params![ref_id, type_id, brand_id], |row| {
+        Ok(DepreciationPrice {
+            ref_id: row.get("ref_id")?,
+            ref_year: row.get("ref_year")?,
+            type_description: row.get("type_description")?,
+            brand_description: row.get("brand_description")?,
+            model_id: row.get("model_id")?,
+            model_description: row.get("model_description")?,
+            fuel_id: row.get("fuel_id")?,
+            model_year: row.get("model_year")?,
+            is_zero_km: row.get("is_zero_km")?,
+            price: row.get("price")?,
+        })
+    })?;
+
+    let mut prices = Vec::new();
+    for price in price_iter {
+        prices.push(price?);
+    }
+    Ok(prices)
+}
+
+// Age in years at the reference date; zero km and next year's models count as new.
+fn vehicle_age(p: &DepreciationPrice) -> i32 {
+    if p.is_zero_km {
+        0
+    } else {
+        (p.ref_year - p.model_year).max(0)
+    }
+}
+
+// Each model (per reference and fuel) is normalised against its newest listed year,
+// then retention is averaged per group and age so expensive models don't dominate.
+pub fn depreciation_curves(
+    prices: &[DepreciationPrice],
+    group_of: impl Fn(&DepreciationPrice) -> String,
+) -> Vec<DepreciationPoint> {
+    let mut baselines: BTreeMap<(i32, i32, Option<i32>), (i32, f64)> = BTreeMap::new();
+    for p in prices {
+        let age = vehicle_age(p);
+        let entry = baselines
+            .entry((p.ref_id, p.model_id, p.fuel_id))
+            .or_insert((age, p.price));
+        if age < entry.0 {
+            *entry = (age, p.price);
+        }
+    }
+
+    // group -> age -> (price sum, retention sum, samples)
+    let mut groups: BTreeMap<String, BTreeMap<i32, (f64, f64, usize)>> = BTreeMap::new();
+    for p in prices {
+        let (_, base) = baselines[&(p.ref_id, p.model_id, p.fuel_id)];
+        if base <= 0.0 {
+            continue;
+        }
+        let bucket = groups
+            .entry(group_of(p))
+            .or_default()
+            .entry(vehicle_age(p))
+            .or_insert((0.0, 0.0, 0));
+        bucket.0 += p.price;
+        bucket.1 += p.price / base;
+        bucket.2 += 1;
+    }
+
+    let mut points = Vec::new();
+    for (group, ages) in groups {
+        let mut previous: Option<f64> = None;
+        for (age, (price_sum, retention_sum, samples)) in ages {
+            let retention = retention_sum / samples as f64;
+            points.push(DepreciationPoint {
+                group: group.clone(),
+                age,
+                avg_price: price_sum / samples as f64,
+                retention: retention * 100.0,
+                yearly_change: pct_change(previous, retention),
+                samples,
+            });
+            previous = Some(retention);
+        }
+    }
+    points
+}
+
+pub fn show_depreciation(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let options = vec![
+        DepreciationScope::Type,
+        DepreciationScope::Brand,
+        DepreciationScope::Model,
+    ];
+    let scope = Select::new("Depreciation curves", options)
+        .with_render_config(render_config())
+        .prompt()?;
+
+    let (type_id, brand_id) = match scope {
+        DepreciationScope::Type => (None, None),
+        DepreciationScope::Brand => match prompt_type(conn)? {
+            Some(t) => (Some(t.id), None),
+            None => return Ok(()),
+        },
+        DepreciationScope::Model => {
+            let Some(t) = prompt_type(conn)? else {
+                return Ok(());
+            };
+            let Some(b) = prompt_brand(conn, t.id)? else {
+                return Ok(());
+            };
+            (Some(t.id), Some(b.id))
+        }
+    };
+
+    let all_references = Confirm::new("Use every loaded reference?")
+        .with_default(false)
+        .prompt()?;
+    let ref_id = if all_references {
+        None
+    } else {
+        match prompt_reference(conn, "Reference", 0)? {
+            Some(r) => Some(r.id),
+            None => {
+                Label::NoReferences.log();
+                return Ok(());
+            }
+        }
+    };
+    let output = prompt_output()?;
+
+    let prices = select_depreciation_prices(conn, ref_id, type_id, brand_id)?;
+    if prices.is_empty() {
+        Label::NoPrices.log();
+        return Ok(());
+    }
+    let points = match scope {
+        DepreciationScope::Type => depreciation_curves(&prices, |p| p.type_description.clone()),
+        DepreciationScope::Brand => depreciation_curves(&prices, |p| p.brand_description.clone()),
+        DepreciationScope::Model => depreciation_curves(&prices, |p| p.model_description.clone()),
+    };
+
+    match output {
+        OutputFormat::Terminal => {
+            let rows: Vec<Vec<String>> = points
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.group.clone(),
+                        p.age.to_string(),
+                        format_price(p.avg_price),
+                        format!("{:.1}%", p.retention),
+                        format_pct(p.yearly_change),
+                        p.samples.to_string(),
+                    ]
+                })
+                .collect();
+            print_table(&DEPRECIATION_HEADERS, &rows);
+        }
+        OutputFormat::Csv => {
+            let rows: Vec<Vec<String>> = points
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.group.clone(),
+                        p.age.to_string(),
+                        format!("{:.2}", p.avg_price),
+                        format!("{:.4}", p.retention),
+                        p.yearly_change
+                            .map(|c| format!("{:.4}", c))
+                            .unwrap_or_default(),
+                        p.samples.to_string(),
+                    ]
+                })
+                .collect();
+            write_csv("depreciation", &DEPRECIATION_HEADERS, &rows)?;
+        }
+        OutputFormat::Json => {
+            write_json("depreciation", &points)?;
+        }
+    }
+    Ok(())
+}
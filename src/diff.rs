@@ -0,0 +1,300 @@
+use crate::export::{write_csv, write_json};
+use crate::inflation::{prompt_deflator, real_price, select_ref_date, Deflator};
+use crate::label::Label;
+use crate::menu::OutputFormat;
+use crate::prompts::{prompt_output, prompt_reference};
+use crate::schema::{DiffEntry, ReferenceDiff};
+use crate::sql::Sql;
+use crate::utils::{format_pct, format_price, pct_change, print_table};
+use rusqlite::{params, Connection};
+
+// Only the largest moves are printed; CSV / JSON carry every row.
+const TERMINAL_LIMIT: usize = 20;
+
+const REAL_HEADERS: [&str; 3] = ["Real Old Price", "Real New Price", "Real Change %"];
+
+const DIFF_HEADERS: [&str; 9] = [
+    "Category",
+    "Change",
+    "Type",
+    "Brand",
+    "Model",
+    "Year",
+    "Old Price",
+    "New Price",
+    "Change %",
+];
+
+fn select_diff_rows(
+    conn: &Connection,
+    sql: Sql,
+    category: &str,
+    present: i32,
+    absent: i32,
+    change: &str,
+    entries: &mut Vec<DiffEntry>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(sql.get().as_str())?;
+    let rows = stmt.query_map(params![present, absent], |row| {
+        Ok(DiffEntry {
+            category: category.to_string(),
+            change: change.to_string(),
+            type_description: row.get("type_description")?,
+            brand: row.get("brand")?,
+            model: row.get("model")?,
+            year: row.get("year")?,
+            old_price: None,
+            new_price: None,
+            pct_change: None,
+            real_old_price: None,
+            real_new_price: None,
+            real_pct_change: None,
+        })
+    })?;
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(())
+}
+
+// Everything that was added, removed or repriced going from one reference to another.
+pub fn diff_references(
+    conn: &Connection,
+    from_ref_id: i32,
+    to_ref_id: i32,
+) -> Result<Vec<DiffEntry>, Box<dyn std::error::Error>> {
+    let mut entries = Vec::new();
+    for (category, sql) in [
+        ("brand", Sql::SelectDiffBrands),
+        ("model", Sql::SelectDiffModels),
+        ("vehicle", Sql::SelectDiffVehicles),
+    ] {
+        select_diff_rows(
+            conn,
+            sql,
+            category,
+            to_ref_id,
+            from_ref_id,
+            "added",
+            &mut entries,
+        )?;
+    }
+    for (category, sql) in [
+        ("brand", Sql::SelectDiffBrands),
+        ("model", Sql::SelectDiffModels),
+        ("vehicle", Sql::SelectDiffVehicles),
+    ] {
+        select_diff_rows(
+            conn,
+            sql,
+            category,
+            from_ref_id,
+            to_ref_id,
+            "removed",
+            &mut entries,
+        )?;
+    }
+
+    let mut stmt = conn.prepare(Sql::SelectDiffPrices.get().as_str())?;
+    let rows = stmt.query_map(params![from_ref_id, to_ref_id], |row| {
+        let old_price: f64 = row.get("old_price")?;
+        let new_price: f64 = row.get("new_price")?;
+        Ok(DiffEntry {
+            category: "price".to_string(),
+            change: "changed".to_string(),
+            type_description: row.get("type_description")?,
+            brand: row.get("brand")?,
+            model: row.get("model")?,
+            year: row.get("year")?,
+            old_price: Some(old_price),
+            new_price: Some(new_price),
+            pct_change: pct_change(Some(old_price), new_price),
+            real_old_price: None,
+            real_new_price: None,
+            real_pct_change: None,
+        })
+    })?;
+    for row in rows {
+        entries.push(row?);
+    }
+    Ok(entries)
+}
+
+// Restates repriced entries in money of the deflator's base month.
+pub fn deflate_diff(diff: &mut ReferenceDiff, deflator: &Deflator) {
+    for e in &mut diff.entries {
+        let (Some(old_price), Some(new_price)) = (e.old_price, e.new_price) else {
+            continue;
+        };
+        e.real_old_price = real_price(deflator, &diff.from_date, old_price);
+        e.real_new_price = real_price(deflator, &diff.to_date, new_price);
+        if let Some(real_new) = e.real_new_price {
+            e.real_pct_change = pct_change(e.real_old_price, real_new);
+        }
+    }
+    diff.inflation = Some(format!("{} ({} money)", deflator.series, deflator.base));
+}
+
+fn diff_headers(diff: &ReferenceDiff) -> Vec<&'static str> {
+    let mut headers = DIFF_HEADERS.to_vec();
+    if diff.inflation.is_some() {
+        headers.extend(REAL_HEADERS);
+    }
+    headers
+}
+
+fn entry_row(e: &DiffEntry, real: bool) -> Vec<String> {
+    let mut row = vec![
+        e.category.clone(),
+        e.change.clone(),
+        e.type_description.clone(),
+        e.brand.clone(),
+        e.model.clone().unwrap_or_default(),
+        e.year.clone().unwrap_or_default(),
+        e.old_price.map(format_price).unwrap_or_default(),
+        e.new_price.map(format_price).unwrap_or_default(),
+        format_pct(e.pct_change),
+    ];
+    if real {
+        row.extend([
+            e.real_old_price.map(format_price).unwrap_or_default(),
+            e.real_new_price.map(format_price).unwrap_or_default(),
+            format_pct(e.real_pct_change),
+        ]);
+    }
+    row
+}
+
+fn print_diff(diff: &ReferenceDiff) {
+    (Label::DiffTitle {
+        from: &diff.from,
+        to: &diff.to,
+    })
+    .log();
+
+    let mut summary = Vec::new();
+    for category in ["brand", "model", "vehicle", "price"] {
+        let count = |change: &str| {
+            diff.entries
+                .iter()
+                .filter(|e| e.category == category && e.change == change)
+                .count()
+                .to_string()
+        };
+        summary.push(vec![
+            category.to_string(),
+            count("added"),
+            count("removed"),
+            count("changed"),
+        ]);
+    }
+    print_table(&["Category", "Added", "Removed", "Changed"], &summary);
+    println!();
+
+    let mut details: Vec<&DiffEntry> = diff
+        .entries
+        .iter()
+        .filter(|e| e.category == "brand" || e.category == "model")
+        .collect();
+    let mut vehicles: Vec<&DiffEntry> = diff
+        .entries
+        .iter()
+        .filter(|e| e.category == "vehicle")
+        .collect();
+    vehicles.truncate(TERMINAL_LIMIT);
+    let mut prices: Vec<&DiffEntry> = diff
+        .entries
+        .iter()
+        .filter(|e| e.category == "price")
+        .collect();
+    prices.sort_by(|a, b| {
+        let a = a.pct_change.unwrap_or(0.0).abs();
+        let b = b.pct_change.unwrap_or(0.0).abs();
+        b.total_cmp(&a)
+    });
+    prices.truncate(TERMINAL_LIMIT);
+    details.extend(vehicles);
+    details.extend(prices);
+
+    let real = diff.inflation.is_some();
+    let rows: Vec<Vec<String>> = details.into_iter().map(|e| entry_row(e, real)).collect();
+    print_table(&diff_headers(diff), &rows);
+    if diff.entries.len() > rows.len() {
+        (Label::TruncatedOutput {
+            shown: &rows.len().to_string(),
+            total: &diff.entries.len().to_string(),
+        })
+        .log();
+    }
+}
+
+pub fn show_reference_diff(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    // References are listed newest first, so the latest two are the defaults.
+    let Some(from) = prompt_reference(conn, "From reference", 1)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let Some(to) = prompt_reference(conn, "To reference", 0)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let output = prompt_output()?;
+
+    let deflator = prompt_deflator(conn)?;
+
+    let mut diff = ReferenceDiff {
+        from_date: select_ref_date(conn, from.id)?,
+        to_date: select_ref_date(conn, to.id)?,
+        from: from.description,
+        to: to.description,
+        entries: diff_references(conn, from.id, to.id)?,
+        inflation: None,
+    };
+    if let Some(d) = &deflator {
+        deflate_diff(&mut diff, d);
+    }
+
+    match output {
+        OutputFormat::Terminal => print_diff(&diff),
+        OutputFormat::Csv => {
+            let rows: Vec<Vec<String>> = diff
+                .entries
+                .iter()
+                .map(|e| {
+                    let mut row = vec![
+                        e.category.clone(),
+                        e.change.clone(),
+                        e.type_description.clone(),
+                        e.brand.clone(),
+                        e.model.clone().unwrap_or_default(),
+                        e.year.clone().unwrap_or_default(),
+                        e.old_price.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+                        e.new_price.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+                        e.pct_change
+                            .map(|p| format!("{:.4}", p))
+                            .unwrap_or_default(),
+                    ];
+                    if diff.inflation.is_some() {
+                        row.extend([
+                            e.real_old_price
+                                .map(|p| format!("{:.2}", p))
+                                .unwrap_or_default(),
+                            e.real_new_price
+                                .map(|p| format!("{:.2}", p))
+                                .unwrap_or_default(),
+                            e.real_pct_change
+                                .map(|p| format!("{:.4}", p))
+                                .unwrap_or_default(),
+                        ]);
+                    }
+                    row
+                })
+                .collect();
+            write_csv("diff", &diff_headers(&diff), &rows)?;
+        }
+        OutputFormat::Json => {
+            write_json("diff", &diff)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use crate::label::Label;
+use chrono::Local;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+const EXPORT_DIR: &str = "exports";
+
+// exports/<name>_<timestamp>.<ext>
+fn export_path(name: &str, ext: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    fs::create_dir_all(EXPORT_DIR)?;
+    let stamp = Local::now().format("%Y%m%d_%H%M%S");
+    Ok(PathBuf::from(EXPORT_DIR).join(format!("{}_{}.{}", name, stamp, ext)))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// Quoted fields may contain the separator and "" for a literal quote.
+pub fn parse_csv_line(line: &str, sep: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == sep && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+pub fn write_csv(
+    name: &str,
+    headers: &[&str],
+    rows: &[Vec<String>],
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = export_path(name, "csv")?;
+    let mut out = String::new();
+    out.push_str(
+        &headers
+            .iter()
+            .map(|h| csv_field(h))
+            .collect::<Vec<String>>()
+            .join(","),
+    );
+    out.push('\n');
+    for row in rows {
+        out.push_str(
+            &row.iter()
+                .map(|c| csv_field(c))
+                .collect::<Vec<String>>()
+                .join(","),
+        );
+        out.push('\n');
+    }
+    fs::write(&path, out)?;
+    (Label::ExportOk {
+        path: &path.display().to_string(),
+    })
+    .log();
+    Ok(path)
+}
+
+pub fn write_json<T: Serialize>(
+    name: &str,
+    value: &T,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = export_path(name, "json")?;
+    fs::write(&path, serde_json::to_string_pretty(value)?)?;
+    (Label::ExportOk {
+        path: &path.display().to_string(),
+    })
+    .log();
+    Ok(path)
+}
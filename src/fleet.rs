@@ -0,0 +1,403 @@
+use crate::export::{parse_csv_line, write_csv, write_json};
+use crate::history::select_price_history;
+use crate::label::Label;
+use crate::menu::OutputFormat;
+use crate::prompts::prompt_output;
+use crate::schema::{Choice, FleetEntry, FleetTotal, FleetValuation, FleetVehicle};
+use crate::search::search;
+use crate::selects::{ambiguous_code, select_choices};
+use crate::sql::Sql;
+use crate::utils::{format_price, print_table, ZERO_KM_YEAR};
+use inquire::Text;
+use rusqlite::{params, Connection};
+use serde_json::Value;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+// Only the first matched rows are printed; CSV / JSON carry every row.
+const TERMINAL_LIMIT: usize = 20;
+
+const FLEET_HEADERS: [&str; 8] = [
+    "Asset",
+    "Input",
+    "Brand",
+    "Model",
+    "Year",
+    "Confidence",
+    "Price",
+    "Note",
+];
+
+// Rows as lowercase column name -> text, from a JSON array of objects or a CSV with a
+// header row (comma or semicolon separated).
+fn read_fleet_rows(path: &str) -> Result<Vec<HashMap<String, String>>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    if path.to_lowercase().ends_with(".json") {
+        let objects: Vec<serde_json::Map<String, Value>> = serde_json::from_str(&content)?;
+        return Ok(objects
+            .into_iter()
+            .map(|object| {
+                object
+                    .into_iter()
+                    .filter_map(|(key, value)| {
+                        let text = match value {
+                            Value::String(s) => s,
+                            Value::Null => return None,
+                            other => other.to_string(),
+                        };
+                        Some((key.to_lowercase(), text))
+                    })
+                    .collect()
+            })
+            .collect());
+    }
+
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let Some(header) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let sep = if header.contains(';') && !header.contains(',') {
+        ';'
+    } else {
+        ','
+    };
+    let headers: Vec<String> = parse_csv_line(header, sep)
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+    Ok(lines
+        .map(|line| {
+            headers
+                .iter()
+                .cloned()
+                .zip(parse_csv_line(line, sep))
+                .collect()
+        })
+        .collect())
+}
+
+fn field(row: &HashMap<String, String>, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .filter_map(|name| row.get(*name))
+        .map(|value| value.trim())
+        .find(|value| !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+// "2015", "0 km" or FIPE's own 32000 for zero km.
+fn parse_model_year(input: &str) -> Option<i32> {
+    let compact = input.to_lowercase().replace(' ', "");
+    if compact == "0km" {
+        return Some(ZERO_KM_YEAR);
+    }
+    compact.parse::<i32>().ok()
+}
+
+fn parse_fleet_vehicle(row: &HashMap<String, String>, index: usize) -> FleetVehicle {
+    FleetVehicle {
+        asset_id: field(row, &["asset_id", "asset", "plate", "placa"])
+            .unwrap_or_else(|| format!("#{}", index + 1)),
+        fipe_code: field(row, &["fipe_code", "code", "codigo_fipe"]),
+        brand: field(row, &["brand", "marca"]),
+        model: field(row, &["model", "modelo"]),
+        model_year: field(row, &["model_year", "year", "ano"]).and_then(|y| parse_model_year(&y)),
+        fuel: field(row, &["fuel", "combustivel"]),
+    }
+}
+
+fn unmatched(vehicle: &FleetVehicle, input: String, note: &str) -> FleetEntry {
+    FleetEntry {
+        asset_id: vehicle.asset_id.clone(),
+        input,
+        brand: None,
+        model: None,
+        year: None,
+        confidence: "unmatched".to_string(),
+        price: None,
+        note: Some(note.to_string()),
+        vehicle_id: None,
+    }
+}
+
+// Confidence: "exact" for a FIPE code, "high" when the brand and model text equal the stored
+// names, "medium" for the best full-text hit; "low" when no fuel was given and several match.
+fn match_vehicle(
+    conn: &Connection,
+    vehicle: &FleetVehicle,
+    fuels: &[Choice],
+) -> Result<FleetEntry, Box<dyn std::error::Error>> {
+    let text = [vehicle.brand.as_deref(), vehicle.model.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<&str>>()
+        .join(" ");
+    let input = format!(
+        "{} {}",
+        vehicle.fipe_code.clone().unwrap_or(text.clone()),
+        vehicle
+            .model_year
+            .map(|y| y.to_string())
+            .unwrap_or_default()
+    )
+    .trim()
+    .to_string();
+
+    let Some(model_year) = vehicle.model_year else {
+        return Ok(unmatched(vehicle, input, "missing or invalid model year"));
+    };
+    let fuel_id = match &vehicle.fuel {
+        Some(fuel) => match fuels.iter().find(|f| {
+            f.id.to_string() == *fuel || f.description.to_lowercase() == fuel.to_lowercase()
+        }) {
+            Some(f) => Some(f.id),
+            None => return Ok(unmatched(vehicle, input, "unknown fuel")),
+        },
+        None => None,
+    };
+
+    let (sql, key, confidence) = if let Some(code) = &vehicle.fipe_code {
+        (
+            Sql::SelectFleetVehicleByCode,
+            code.clone(),
+            "exact".to_string(),
+        )
+    } else if !text.is_empty() {
+        let Some(hit) = search(conn, &text, 10)?
+            .into_iter()
+            .find(|r| r.kind == "model")
+        else {
+            return Ok(unmatched(vehicle, input, "no matching model"));
+        };
+        let same = |stored: &str, given: &Option<String>| {
+            given
+                .as_ref()
+                .is_none_or(|g| stored.to_lowercase() == g.to_lowercase())
+        };
+        let confidence = if same(&hit.brand, &vehicle.brand) && same(&hit.model, &vehicle.model) {
+            "high"
+        } else {
+            "medium"
+        };
+        (
+            Sql::SelectFleetVehicleByModel,
+            hit.entity_id.to_string(),
+            confidence.to_string(),
+        )
+    } else {
+        return Ok(unmatched(vehicle, input, "no FIPE code or brand / model"));
+    };
+
+    let mut stmt = conn.prepare(sql.get().as_str())?;
+    let candidates = stmt
+        .query_map(params![key, model_year, fuel_id], |row| {
+            Ok((
+                row.get::<_, i32>("id")?,
+                row.get::<_, String>("brand")?,
+                row.get::<_, String>("model")?,
+                row.get::<_, String>("year")?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    let Some((vehicle_id, brand, model, year)) = candidates.first().cloned() else {
+        return Ok(unmatched(vehicle, input, "model year / fuel not loaded"));
+    };
+    // With a fuel given, several rows are the same code listed under two models.
+    if vehicle.fipe_code.is_some() && fuel_id.is_some() && candidates.len() > 1 {
+        let note = ambiguous_code(&key, model_year, candidates.len()).to_string();
+        return Ok(unmatched(vehicle, input, &note));
+    }
+    let (confidence, note) = if candidates.len() > 1 {
+        (
+            "low".to_string(),
+            Some(format!(
+                "no fuel given; first of {} matches",
+                candidates.len()
+            )),
+        )
+    } else {
+        (confidence, None)
+    };
+    Ok(FleetEntry {
+        asset_id: vehicle.asset_id.clone(),
+        input,
+        brand: Some(brand),
+        model: Some(model),
+        year: Some(year),
+        confidence,
+        price: None,
+        note,
+        vehicle_id: Some(vehicle_id),
+    })
+}
+
+// Matches every row, prices matched vehicles in the latest reference any of them is priced in,
+// and totals the fleet for every reference month.
+pub fn value_fleet(
+    conn: &Connection,
+    rows: &[HashMap<String, String>],
+) -> Result<FleetValuation, Box<dyn std::error::Error>> {
+    let fuels = select_choices(conn, Sql::SelectFuelChoices, [])?;
+    let mut entries = Vec::new();
+    for (index, row) in rows.iter().enumerate() {
+        entries.push(match_vehicle(
+            conn,
+            &parse_fleet_vehicle(row, index),
+            &fuels,
+        )?);
+    }
+
+    let mut histories: HashMap<i32, BTreeMap<String, f64>> = HashMap::new();
+    let mut totals: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+    for entry in &entries {
+        let Some(vehicle_id) = entry.vehicle_id else {
+            continue;
+        };
+        if let Entry::Vacant(slot) = histories.entry(vehicle_id) {
+            slot.insert(
+                select_price_history(conn, vehicle_id)?
+                    .into_iter()
+                    .map(|h| (h.ref_date.chars().take(7).collect(), h.price))
+                    .collect(),
+            );
+        }
+        for (reference, price) in &histories[&vehicle_id] {
+            let total = totals.entry(reference.clone()).or_insert((0, 0.0));
+            total.0 += 1;
+            total.1 += price;
+        }
+    }
+
+    let reference = totals.keys().next_back().cloned();
+    if let Some(reference) = &reference {
+        for entry in &mut entries {
+            entry.price = entry
+                .vehicle_id
+                .and_then(|id| histories[&id].get(reference).copied());
+        }
+    }
+    Ok(FleetValuation {
+        reference,
+        entries,
+        totals: totals
+            .into_iter()
+            .map(|(reference, (vehicles, total))| FleetTotal {
+                reference,
+                vehicles,
+                total,
+            })
+            .collect(),
+    })
+}
+
+fn entry_row(e: &FleetEntry, price: String) -> Vec<String> {
+    vec![
+        e.asset_id.clone(),
+        e.input.clone(),
+        e.brand.clone().unwrap_or_default(),
+        e.model.clone().unwrap_or_default(),
+        e.year.clone().unwrap_or_default(),
+        e.confidence.clone(),
+        price,
+        e.note.clone().unwrap_or_default(),
+    ]
+}
+
+fn print_valuation(valuation: &FleetValuation) {
+    let (matched, unmatched): (Vec<&FleetEntry>, Vec<&FleetEntry>) = valuation
+        .entries
+        .iter()
+        .partition(|e| e.vehicle_id.is_some());
+    (Label::FleetTitle {
+        reference: valuation.reference.as_deref().unwrap_or("-"),
+        matched: &matched.len().to_string(),
+        total: &valuation.entries.len().to_string(),
+    })
+    .log();
+
+    let rows: Vec<Vec<String>> = matched
+        .iter()
+        .take(TERMINAL_LIMIT)
+        .map(|e| entry_row(e, e.price.map(format_price).unwrap_or_default()))
+        .collect();
+    print_table(&FLEET_HEADERS, &rows);
+    if matched.len() > rows.len() {
+        (Label::TruncatedOutput {
+            shown: &rows.len().to_string(),
+            total: &matched.len().to_string(),
+        })
+        .log();
+    }
+    if !unmatched.is_empty() {
+        println!();
+        let rows: Vec<Vec<String>> = unmatched
+            .iter()
+            .map(|e| {
+                vec![
+                    e.asset_id.clone(),
+                    e.input.clone(),
+                    e.note.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        print_table(&["Asset", "Input", "Unmatched"], &rows);
+    }
+    println!();
+    let rows: Vec<Vec<String>> = valuation
+        .totals
+        .iter()
+        .map(|t| {
+            vec![
+                t.reference.clone(),
+                t.vehicles.to_string(),
+                format_price(t.total),
+            ]
+        })
+        .collect();
+    print_table(&["Reference", "Vehicles", "Total"], &rows);
+}
+
+pub fn show_fleet_valuation(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let path = Text::new("Fleet file (CSV or JSON):").prompt()?;
+    let rows = match read_fleet_rows(path.trim()) {
+        Ok(rows) => rows,
+        Err(e) => {
+            (Label::FileReadError {
+                message: &e.to_string(),
+            })
+            .log();
+            return Ok(());
+        }
+    };
+    let output = prompt_output()?;
+    let valuation = value_fleet(conn, &rows)?;
+
+    match output {
+        OutputFormat::Terminal => print_valuation(&valuation),
+        OutputFormat::Csv => {
+            let rows: Vec<Vec<String>> = valuation
+                .entries
+                .iter()
+                .map(|e| entry_row(e, e.price.map(|p| format!("{:.2}", p)).unwrap_or_default()))
+                .collect();
+            write_csv("fleet_valuation", &FLEET_HEADERS, &rows)?;
+            let totals: Vec<Vec<String>> = valuation
+                .totals
+                .iter()
+                .map(|t| {
+                    vec![
+                        t.reference.clone(),
+                        t.vehicles.to_string(),
+                        format!("{:.2}", t.total),
+                    ]
+                })
+                .collect();
+            write_csv("fleet_totals", &["Reference", "Vehicles", "Total"], &totals)?;
+        }
+        OutputFormat::Json => {
+            write_json("fleet_valuation", &valuation)?;
+        }
+    }
+    Ok(())
+}
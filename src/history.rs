@@ -0,0 +1,136 @@
+use crate::export::{write_csv, write_json};
+use crate::inflation::{prompt_deflator, real_price};
+use crate::label::Label;
+use crate::menu::OutputFormat;
+use crate::prompts::{prompt_output, prompt_vehicle};
+use crate::schema::{PriceHistory, VehicleHistory};
+use crate::sql::Sql;
+use crate::utils::{format_pct, format_price, pct_change, print_table};
+use rusqlite::Connection;
+
+const HISTORY_HEADERS: [&str; 5] = ["Reference", "Code", "Price", "MoM", "YoY"];
+
+// Price of one canonical vehicle in every loaded reference, oldest first.
+pub fn select_price_history(
+    conn: &Connection,
+    vehicle_id: i32,
+) -> Result<Vec<PriceHistory>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectPriceHistory.get().as_str())?;
+    let history_iter = stmt.query_map([vehicle_id], |row| {
+        let price: f64 = row.get("price")?;
+        Ok(PriceHistory {
+            ref_date: row.get("ref_date")?,
+            fipe: row.get("fipe")?,
+            price,
+            month_change: pct_change(row.get("month_ago_price")?, price),
+            year_change: pct_change(row.get("year_ago_price")?, price),
+            real_price: None,
+        })
+    })?;
+
+    let mut history = Vec::new();
+    for h in history_iter {
+        history.push(h?);
+    }
+    Ok(history)
+}
+
+fn history_headers(history: &VehicleHistory) -> Vec<&'static str> {
+    let mut headers = HISTORY_HEADERS.to_vec();
+    if history.inflation.is_some() {
+        headers.push("Real Price");
+    }
+    headers
+}
+
+fn print_history(history: &VehicleHistory) {
+    (Label::PriceHistoryTitle {
+        vehicle: &history.vehicle,
+    })
+    .log();
+    let rows: Vec<Vec<String>> = history
+        .entries
+        .iter()
+        .map(|h| {
+            let mut row = vec![
+                h.ref_date.chars().take(7).collect(),
+                h.fipe.clone(),
+                format_price(h.price),
+                format_pct(h.month_change),
+                format_pct(h.year_change),
+            ];
+            if history.inflation.is_some() {
+                row.push(
+                    h.real_price
+                        .map(format_price)
+                        .unwrap_or_else(|| "-".to_string()),
+                );
+            }
+            row
+        })
+        .collect();
+    print_table(&history_headers(history), &rows);
+}
+
+pub fn show_price_history(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let Some((vehicle_id, vehicle)) = prompt_vehicle(conn)? else {
+        Label::VehicleNotFound.log();
+        return Ok(());
+    };
+
+    let entries = select_price_history(conn, vehicle_id)?;
+    if entries.is_empty() {
+        Label::NoPrices.log();
+        return Ok(());
+    }
+    let output = prompt_output()?;
+    let deflator = prompt_deflator(conn)?;
+
+    let mut history = VehicleHistory {
+        vehicle,
+        entries,
+        inflation: None,
+    };
+    if let Some(d) = &deflator {
+        for h in &mut history.entries {
+            h.real_price = real_price(d, &h.ref_date, h.price);
+        }
+        history.inflation = Some(format!("{} ({} money)", d.series, d.base));
+    }
+
+    match output {
+        OutputFormat::Terminal => print_history(&history),
+        OutputFormat::Csv => {
+            let rows: Vec<Vec<String>> = history
+                .entries
+                .iter()
+                .map(|h| {
+                    let mut row = vec![
+                        h.ref_date.chars().take(7).collect(),
+                        h.fipe.clone(),
+                        format!("{:.2}", h.price),
+                        h.month_change
+                            .map(|p| format!("{:.4}", p))
+                            .unwrap_or_default(),
+                        h.year_change
+                            .map(|p| format!("{:.4}", p))
+                            .unwrap_or_default(),
+                    ];
+                    if history.inflation.is_some() {
+                        row.push(
+                            h.real_price
+                                .map(|p| format!("{:.2}", p))
+                                .unwrap_or_default(),
+                        );
+                    }
+                    row
+                })
+                .collect();
+            write_csv("history", &history_headers(&history), &rows)?;
+        }
+        OutputFormat::Json => {
+            write_json("history", &history)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+use crate::export::{write_csv, write_json};
+use crate::label::Label;
+use crate::menu::{IndexScope, OutputFormat};
+use crate::prompts::{prompt_output, prompt_reference, prompt_type, render_config};
+use crate::schema::{IndexPoint, IndexRelative};
+use crate::sql::Sql;
+use crate::utils::{format_pct, pct_change, print_table};
+use inquire::Select;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+
+const INDEX_HEADERS: [&str; 5] = ["Group", "Reference", "Index", "Monthly Change", "Samples"];
+
+pub fn select_index_relatives(
+    conn: &Connection,
+    type_id: Option<i32>,
+) -> Result<Vec<IndexRelative>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectIndexRelatives.get().as_str())?;
+    let relative_iter = stmt.query_map([type_id], |row| {
+        Ok(IndexRelative {
+            ref_month: row.get("ref_month")?,
+            type_description: row.get("type_description")?,
+            brand_description: row.get("brand_description")?,
+            fuel_description: row.get("fuel_description")?,
+            relative: row.get("relative")?,
+        })
+    })?;
+
+    let mut relatives = Vec::new();
+    for relative in relative_iter {
+        relatives.push(relative?);
+    }
+    Ok(relatives)
+}
+
+// Chained index: each month's link is the geometric mean of the price relatives of vehicles
+// priced in that month and the one before, so only like-for-like vehicles move the index.
+// A month without links carries the index forward. Series are rebased to 100 at `base`
+// ("YYYY-MM"); groups that start after the base month are left out.
+pub fn index_series(
+    relatives: &[IndexRelative],
+    base: &str,
+    group_of: impl Fn(&IndexRelative) -> String,
+) -> Vec<IndexPoint> {
+    // group -> month -> (log relative sum, samples)
+    let mut groups: BTreeMap<String, BTreeMap<String, (f64, usize)>> = BTreeMap::new();
+    for r in relatives {
+        let link = groups
+            .entry(group_of(r))
+            .or_default()
+            .entry(r.ref_month.clone())
+            .or_insert((0.0, 0));
+        link.0 += r.relative.ln();
+        link.1 += 1;
+    }
+
+    let mut points = Vec::new();
+    for (group, links) in groups {
+        // The month before the first link starts the chain.
+        let Some(first) = links.keys().next().and_then(|m| previous_month(m)) else {
+            continue;
+        };
+        let mut chained = vec![(first, 1.0, 0)];
+        let mut level = 1.0;
+        for (month, (log_sum, samples)) in links {
+            level *= (log_sum / samples as f64).exp();
+            chained.push((month, level, samples));
+        }
+
+        let Some(base_level) = chained
+            .iter()
+            .rev()
+            .find(|(month, _, _)| month.as_str() <= base)
+            .map(|(_, level, _)| *level)
+        else {
+            continue;
+        };
+        let mut previous: Option<f64> = None;
+        for (month, level, samples) in chained {
+            let index = level / base_level * 100.0;
+            points.push(IndexPoint {
+                group: group.clone(),
+                reference: month,
+                index,
+                monthly_change: pct_change(previous, index),
+                samples,
+            });
+            previous = Some(index);
+        }
+    }
+    points
+}
+
+// "2024-01" -> "2023-12"
+fn previous_month(month: &str) -> Option<String> {
+    let (year, month) = month.split_once('-')?;
+    let (year, month): (i32, u32) = (year.parse().ok()?, month.parse().ok()?);
+    Some(if month == 1 {
+        format!("{}-12", year - 1)
+    } else {
+        format!("{}-{:02}", year, month - 1)
+    })
+}
+
+// Index for the chosen scope: `type_id` narrows the market to one vehicle type.
+pub fn price_index(
+    conn: &Connection,
+    scope: &IndexScope,
+    type_id: Option<i32>,
+    base: &str,
+) -> Result<Vec<IndexPoint>, Box<dyn std::error::Error>> {
+    let relatives = select_index_relatives(conn, type_id)?;
+    Ok(match scope {
+        IndexScope::Market => index_series(&relatives, base, |_| "Market".to_string()),
+        IndexScope::Type => index_series(&relatives, base, |r| r.type_description.clone()),
+        IndexScope::Brand => index_series(&relatives, base, |r| r.brand_description.clone()),
+        IndexScope::Fuel => index_series(&relatives, base, |r| r.fuel_description.clone()),
+    })
+}
+
+pub fn show_price_index(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let options = vec![
+        IndexScope::Market,
+        IndexScope::Type,
+        IndexScope::Brand,
+        IndexScope::Fuel,
+    ];
+    let scope = Select::new("Price index", options)
+        .with_render_config(render_config())
+        .prompt()?;
+    let type_id = match scope {
+        IndexScope::Brand => match prompt_type(conn)? {
+            Some(t) => Some(t.id),
+            None => return Ok(()),
+        },
+        _ => None,
+    };
+    // References are listed newest first; the oldest is the default base.
+    let Some(base) = prompt_reference(conn, "Base month (index = 100)", usize::MAX)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let output = prompt_output()?;
+
+    let base: String = base.description.chars().take(7).collect();
+    let points = price_index(conn, &scope, type_id, &base)?;
+    if points.is_empty() {
+        Label::NoPrices.log();
+        return Ok(());
+    }
+
+    match output {
+        OutputFormat::Terminal => {
+            let rows: Vec<Vec<String>> = points
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.group.clone(),
+                        p.reference.clone(),
+                        format!("{:.2}", p.index),
+                        format_pct(p.monthly_change),
+                        p.samples.to_string(),
+                    ]
+                })
+                .collect();
+            print_table(&INDEX_HEADERS, &rows);
+        }
+        OutputFormat::Csv => {
+            let rows: Vec<Vec<String>> = points
+                .iter()
+                .map(|p| {
+                    vec![
+                        p.group.clone(),
+                        p.reference.clone(),
+                        format!("{:.4}", p.index),
+                        p.monthly_change
+                            .map(|c| format!("{:.4}", c))
+                            .unwrap_or_default(),
+                        p.samples.to_string(),
+                    ]
+                })
+                .collect();
+            write_csv("price_index", &INDEX_HEADERS, &rows)?;
+        }
+        OutputFormat::Json => {
+            write_json("price_index", &points)?;
+        }
+    }
+    Ok(())
+}
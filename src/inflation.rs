@@ -0,0 +1,153 @@
+use crate::export::parse_csv_line;
+use crate::label::Label;
+use crate::prompts::{prompt_choice, prompt_reference};
+use crate::selects::select_choices;
+use crate::sql::Sql;
+use inquire::{Confirm, Text};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+use std::fs;
+
+// Index levels of one series by "YYYY-MM", and the month prices are expressed in.
+pub struct Deflator {
+    pub series: String,
+    pub base: String,
+    pub levels: BTreeMap<String, f64>,
+}
+
+// "2024-01", "2024-01-01" or "01/2024" -> "2024-01-01"
+fn parse_month(input: &str) -> Option<String> {
+    let input = input.trim();
+    let (year, month) = match input.split_once('/') {
+        Some((month, year)) => (year, month),
+        None => {
+            let mut parts = input.splitn(3, '-');
+            (parts.next()?, parts.next()?)
+        }
+    };
+    let (year, month): (i32, u32) = (year.parse().ok()?, month.parse().ok()?);
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    Some(format!("{}-{:02}-01", year, month))
+}
+
+// Accepts "6543.21" and Brazilian "6.543,21".
+fn parse_level(input: &str) -> Option<f64> {
+    let input = input.trim();
+    if input.contains(',') {
+        input.replace('.', "").replace(',', ".").parse().ok()
+    } else {
+        input.parse().ok()
+    }
+}
+
+// Reads month / index level pairs from the first two columns; rows that don't parse
+// (headers included) are skipped. Re-importing a series overwrites its months.
+pub fn import_inflation(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let series = Text::new("Series name (e.g. IPCA):").prompt()?;
+    let path = Text::new("CSV file (month, index level):").prompt()?;
+    let content = match fs::read_to_string(path.trim()) {
+        Ok(content) => content,
+        Err(e) => {
+            (Label::FileReadError {
+                message: &e.to_string(),
+            })
+            .log();
+            return Ok(());
+        }
+    };
+
+    let series = series.trim().to_uppercase();
+    let tx = conn.unchecked_transaction()?;
+    let mut count = 0;
+    for line in content.lines() {
+        let sep = if line.contains(';') { ';' } else { ',' };
+        let fields = parse_csv_line(line, sep);
+        let (Some(month), Some(level)) = (
+            fields.first().and_then(|m| parse_month(m)),
+            fields.get(1).and_then(|v| parse_level(v)),
+        ) else {
+            continue;
+        };
+        tx.execute(
+            Sql::InsertInflationIndex.get().as_str(),
+            params![series, month, level],
+        )?;
+        count += 1;
+    }
+    tx.commit()?;
+    (Label::InflationImportOk {
+        series: &series,
+        count: &count.to_string(),
+    })
+    .log();
+    Ok(())
+}
+
+pub fn select_deflator(
+    conn: &Connection,
+    series: &str,
+    base: &str,
+) -> Result<Deflator, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectInflationIndex.get().as_str())?;
+    let levels = stmt
+        .query_map([series], |row| Ok((row.get("month")?, row.get("value")?)))?
+        .collect::<Result<BTreeMap<String, f64>, rusqlite::Error>>()?;
+    Ok(Deflator {
+        series: series.to_string(),
+        base: base.to_string(),
+        levels,
+    })
+}
+
+pub fn select_ref_date(
+    conn: &Connection,
+    ref_id: i32,
+) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(
+        conn.query_row(Sql::SelectReferenceDate.get().as_str(), [ref_id], |row| {
+            row.get("ref_date")
+        })?,
+    )
+}
+
+// Price of reference month `ref_date` in money of the base month; None when the series
+// doesn't cover either month.
+pub fn real_price(deflator: &Deflator, ref_date: &str, price: f64) -> Option<f64> {
+    let month: String = ref_date.chars().take(7).collect();
+    let level = deflator.levels.get(&month)?;
+    let base = deflator.levels.get(&deflator.base)?;
+    Some(price * base / level)
+}
+
+// Asks for a series and base month when any series was imported; None keeps nominal prices.
+pub fn prompt_deflator(conn: &Connection) -> Result<Option<Deflator>, Box<dyn std::error::Error>> {
+    let series = select_choices(conn, Sql::SelectInflationSeries, [])?;
+    if series.is_empty() {
+        return Ok(None);
+    }
+    if !Confirm::new("Adjust prices for inflation?")
+        .with_default(false)
+        .prompt()?
+    {
+        return Ok(None);
+    }
+    let Some(series) = prompt_choice("Inflation series", series)? else {
+        return Ok(None);
+    };
+    let Some(base) = prompt_reference(conn, "Prices in money of", 0)? else {
+        return Ok(None);
+    };
+    let base: String = select_ref_date(conn, base.id)?.chars().take(7).collect();
+    let deflator = select_deflator(conn, &series.description, &base)?;
+    if !deflator.levels.contains_key(&deflator.base) {
+        (Label::InflationMonthMissing {
+            series: &deflator.series,
+            month: &deflator.base,
+        })
+        .log();
+        return Ok(None);
+    }
+    Ok(Some(deflator))
+}
@@ -6,6 +6,7 @@ pub enum Label<'a> {
     Header {
         db_status: &'a str,
         last_update: &'a str,
+        latest_remote: &'a str,
     },
 
     // setup_db
@@ -14,6 +15,10 @@ pub enum Label<'a> {
         table_name: &'a str,
     },
     CreateIndexes,
+    CreateViews,
+    MigrationOk {
+        version: &'a str,
+    },
     // requests
     ClientFail,
     ResponseError {
@@ -31,6 +36,9 @@ pub enum Label<'a> {
     UniqueConstraint {
         fipe: &'a str,
     },
+    MalformedPrice {
+        valor: &'a str,
+    },
     TableNotExist,
     NoResults,
     InsertReference {
@@ -57,6 +65,104 @@ pub enum Label<'a> {
         modelo: &'a str,
         ano: &'a str,
     },
+    InsertPrice {
+        tipo: &'a str,
+        referencia: &'a str,
+        marca: &'a str,
+        modelo: &'a str,
+        ano: &'a str,
+        valor: &'a str,
+    },
+    PriceHistoryTitle {
+        vehicle: &'a str,
+    },
+    VehicleNotFound,
+    NoPrices,
+    NoAnomalies,
+    AnomaliesFound {
+        jumps: &'a str,
+        inversions: &'a str,
+    },
+    PriceNotFound,
+    NoReferences,
+    DiffTitle {
+        from: &'a str,
+        to: &'a str,
+    },
+    TruncatedOutput {
+        shown: &'a str,
+        total: &'a str,
+    },
+    ExportOk {
+        path: &'a str,
+    },
+    FleetTitle {
+        reference: &'a str,
+        matched: &'a str,
+        total: &'a str,
+    },
+    FileReadError {
+        message: &'a str,
+    },
+    InflationImportOk {
+        series: &'a str,
+        count: &'a str,
+    },
+    InflationMonthMissing {
+        series: &'a str,
+        month: &'a str,
+    },
+    SearchTitle {
+        query: &'a str,
+        count: &'a str,
+    },
+    UpToDate {
+        reference: &'a str,
+    },
+    NewReference {
+        reference: &'a str,
+    },
+    NothingLoaded,
+    ReloadTitle {
+        count: &'a str,
+    },
+    NoReloadChanges,
+    YearsCorrected {
+        added: &'a str,
+        removed: &'a str,
+    },
+    DeleteOk {
+        what: &'a str,
+    },
+    HealthTitle {
+        complete: &'a str,
+        total: &'a str,
+    },
+    NoIssues,
+    NewVariants {
+        count: &'a str,
+    },
+    LifecycleTitle {
+        from: &'a str,
+        to: &'a str,
+    },
+    NoLifecycleEvents,
+    NoPendingAliases,
+    WatchAdded {
+        vehicle: &'a str,
+    },
+    WatchlistEmpty,
+    WatchAlertTitle {
+        count: &'a str,
+        threshold: &'a str,
+    },
+    WebhookError {
+        message: &'a str,
+    },
+    WebhookAdded {
+        url: &'a str,
+    },
+    NoWebhooks,
     PressKeyContinue,
 }
 
@@ -66,14 +172,17 @@ impl<'a> fmt::Display for Label<'a> {
             Label::Header {
                 db_status,
                 last_update,
+                latest_remote,
             } => write!(
                 f,
-                "{}\n{} {}\n{} {}\n",
+                "{}\n{} {}\n{} {}\n{} {}\n",
                 "FIPE_rs".bold().bright_cyan(),
                 "DB Status:".bold().yellow(),
                 db_status.bold(),
                 "Last Update:".bold().black().dimmed(),
-                last_update.italic().black().dimmed()
+                last_update.italic().black().dimmed(),
+                "Latest FIPE Reference:".bold().black().dimmed(),
+                latest_remote.italic().black().dimmed()
             ),
             Label::ClientFail => write!(
                 f,
@@ -109,12 +218,34 @@ impl<'a> fmt::Display for Label<'a> {
                 "[SUCCESS]".bold().bright_green(),
                 "Created indexes.".bold()
             ),
+            Label::CreateViews => write!(
+                f,
+                "{}: {}",
+                "[SUCCESS]".bold().bright_green(),
+                "Created views.".bold()
+            ),
+            Label::MigrationOk { version } => write!(
+                f,
+                "{}: {}",
+                "[SUCCESS]".bold().bright_green(),
+                format!("Database migrated to schema version {}.", version.blue()).bold()
+            ),
             Label::LoadOk { entity } => write!(
                 f,
                 "  {}:  {}",
                 "[SUCCESS]".bold().bright_green(),
                 format!(" {} successfully loaded.", entity.blue()).bold()
             ),
+            Label::MalformedPrice { valor } => write!(
+                f,
+                "{}: {} {}",
+                "[WARN]".bold().yellow(),
+                valor.italic().black().dimmed(),
+                "is not a valid price; logged to failed_requests."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
             Label::UniqueConstraint { fipe } => {
                 write!(
                     f,
@@ -201,6 +332,303 @@ impl<'a> fmt::Display for Label<'a> {
                     ano
                 )
             }
+            Label::InsertPrice {
+                tipo,
+                referencia,
+                marca,
+                modelo,
+                ano,
+                valor,
+            } => {
+                write!(
+                    f,
+                    "   {}:  {} | {} | {} | {} | {} | {}",
+                    "[SUCCESS]".bold().bright_green(),
+                    tipo.bold().blue(),
+                    referencia.bold().yellow(),
+                    marca.bold().red(),
+                    modelo.bold().magenta(),
+                    ano,
+                    valor.bold().green()
+                )
+            }
+            Label::PriceHistoryTitle { vehicle } => writeln!(
+                f,
+                "{} {}",
+                "Price history:".bold().bright_cyan(),
+                vehicle.bold()
+            ),
+            Label::VehicleNotFound => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                "No matching vehicle in the database."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::PriceNotFound => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                "FIPE has no price for this code, model year and fuel in that reference."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::NoAnomalies => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "No price anomalies flagged.".italic().black().dimmed()
+            ),
+            Label::AnomaliesFound { jumps, inversions } => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                format!(
+                    "{} price jumps and {} model year inversions flagged. See Reports > Price Anomalies.",
+                    jumps, inversions
+                )
+                .italic()
+                .black()
+                .dimmed()
+            ),
+            Label::NoPrices => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                "No prices loaded for this selection. Run Load Prices first."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::NoReferences => write!(
+                f,
+                "{}: {}",
+                "[ERROR]".bold().bright_red(),
+                "No references in database. Run Load References first."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::DiffTitle { from, to } => writeln!(
+                f,
+                "{} {} {} {}",
+                "Reference diff:".bold().bright_cyan(),
+                from.bold().yellow(),
+                "->".dimmed(),
+                to.bold().yellow()
+            ),
+            Label::TruncatedOutput { shown, total } => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                format!(
+                    "Showing {} of {} rows. Export to CSV or JSON for the full report.",
+                    shown, total
+                )
+                .italic()
+                .black()
+                .dimmed()
+            ),
+            Label::FleetTitle {
+                reference,
+                matched,
+                total,
+            } => writeln!(
+                f,
+                "{} {}",
+                format!("Fleet valuation ({}):", reference).bold().bright_cyan(),
+                format!("{} of {} vehicles matched", matched, total).bold()
+            ),
+            Label::FileReadError { message } => write!(
+                f,
+                "{}: {}",
+                "[ERROR]".bold().bright_red(),
+                format!("Could not read the file: {}", message)
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::InflationImportOk { series, count } => write!(
+                f,
+                "  {}:  {}",
+                "[SUCCESS]".bold().bright_green(),
+                format!("{} months of {} imported.", count, series.blue()).bold()
+            ),
+            Label::InflationMonthMissing { series, month } => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                format!("{} has no level for {}; showing nominal prices.", series, month)
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::ExportOk { path } => write!(
+                f,
+                "  {}:  {}",
+                "[SUCCESS]".bold().bright_green(),
+                format!("Report written to {}.", path.blue()).bold()
+            ),
+            Label::SearchTitle { query, count } => writeln!(
+                f,
+                "{} {} {}",
+                "Search:".bold().bright_cyan(),
+                query.bold().yellow(),
+                format!("({} matches)", count).italic().black().dimmed()
+            ),
+            Label::LifecycleTitle { from, to } => writeln!(
+                f,
+                "{} {} {} {}",
+                "Launches & discontinuations:".bold().bright_cyan(),
+                from.bold().yellow(),
+                "->".dimmed(),
+                to.bold().yellow()
+            ),
+            Label::NoLifecycleEvents => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "No launches or discontinuations in this period."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::UpToDate { reference } => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                format!("Database has the latest FIPE reference ({}).", reference)
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::NewReference { reference } => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                format!("FIPE published {}, which is not fully loaded yet.", reference)
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::ReloadTitle { count } => writeln!(
+                f,
+                "{} {}",
+                "Reload:".bold().bright_cyan(),
+                format!("{} differences", count).bold()
+            ),
+            Label::NoReloadChanges => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "Reload found no differences.".italic().black().dimmed()
+            ),
+            Label::YearsCorrected { added, removed } => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                format!(
+                    "Per-reference years: {} added, {} removed as not listed in their reference.",
+                    added, removed
+                )
+                .italic()
+                .black()
+                .dimmed()
+            ),
+            Label::NothingLoaded => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "Nothing loaded for this selection."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::WatchAdded { vehicle } => write!(
+                f,
+                "  {}:  {}",
+                "[SUCCESS]".bold().bright_green(),
+                format!("{} added to the watchlist.", vehicle.blue()).bold()
+            ),
+            Label::WatchlistEmpty => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "The watchlist is empty.".italic().black().dimmed()
+            ),
+            Label::WatchAlertTitle { count, threshold } => writeln!(
+                f,
+                "{} {}",
+                "Watchlist alerts:".bold().bright_cyan(),
+                format!("{} vehicles moved {}% or more", count, threshold).bold()
+            ),
+            Label::WebhookError { message } => write!(
+                f,
+                "{}: {}",
+                "[WARN]".bold().yellow(),
+                format!("Webhook not delivered: {}", message)
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::WebhookAdded { url } => write!(
+                f,
+                "  {}:  {}",
+                "[SUCCESS]".bold().bright_green(),
+                format!("Webhook {} saved.", url.blue()).bold()
+            ),
+            Label::NoWebhooks => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "No webhooks configured.".italic().black().dimmed()
+            ),
+            Label::DeleteOk { what } => write!(
+                f,
+                "{}: {}",
+                "[SUCCESS]".bold().bright_green(),
+                format!("Deleted {} and everything loaded under it.", what)
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::HealthTitle { complete, total } => writeln!(
+                f,
+                "{} {}",
+                "Database health:".bold().bright_cyan(),
+                format!("{} of {} reference / type pairs fully loaded", complete, total).bold()
+            ),
+            Label::NoIssues => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "No partial loads or orphans found."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
+            Label::NewVariants { count } => writeln!(
+                f,
+                "\n{}: {}",
+                "[INFO]".bold().blue(),
+                format!(
+                    "{} new name variants detected. Review them under Maintenance.",
+                    count
+                )
+                .bold()
+            ),
+            Label::NoPendingAliases => write!(
+                f,
+                "{}: {}",
+                "[INFO]".bold().blue(),
+                "No name aliases waiting for review."
+                    .italic()
+                    .black()
+                    .dimmed()
+            ),
             Label::PressKeyContinue => write!(
                 f,
                 "{}",
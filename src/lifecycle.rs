@@ -0,0 +1,120 @@
+use crate::export::{write_csv, write_json};
+use crate::label::Label;
+use crate::menu::OutputFormat;
+use crate::prompts::{prompt_brand, prompt_output, prompt_reference, prompt_type};
+use crate::schema::LifecycleEvent;
+use crate::sql::Sql;
+use crate::utils::print_table;
+use inquire::Confirm;
+use rusqlite::{params, Connection};
+
+const LIFECYCLE_HEADERS: [&str; 7] = [
+    "Event",
+    "Kind",
+    "Reference",
+    "Type",
+    "Brand",
+    "Model",
+    "Year",
+];
+
+// Model and model-year launches / discontinuations between two references, oldest first.
+pub fn select_lifecycle(
+    conn: &Connection,
+    from_ref_id: i32,
+    to_ref_id: i32,
+    type_id: i32,
+    brand_id: Option<i32>,
+) -> Result<Vec<LifecycleEvent>, Box<dyn std::error::Error>> {
+    let mut events = Vec::new();
+    for sql in [Sql::SelectModelLifecycle, Sql::SelectVehicleLifecycle] {
+        let mut stmt = conn.prepare(sql.get().as_str())?;
+        let rows = stmt.query_map(params![from_ref_id, to_ref_id, type_id, brand_id], |row| {
+            Ok(LifecycleEvent {
+                event: row.get("event")?,
+                kind: row.get("kind")?,
+                ref_date: row.get("ref_date")?,
+                type_description: row.get("type_description")?,
+                brand: row.get("brand")?,
+                model: row.get("model")?,
+                year: row.get("year")?,
+            })
+        })?;
+        for row in rows {
+            events.push(row?);
+        }
+    }
+    events.sort_by(|a, b| a.ref_date.cmp(&b.ref_date));
+    Ok(events)
+}
+
+// Recomputes first / last seen after deletes, dropping rows for anything no longer listed.
+pub fn refresh_lifecycle(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(Sql::RefreshModelLifecycle.get().as_str(), [])?;
+    conn.execute(Sql::RefreshVehicleLifecycle.get().as_str(), [])?;
+    conn.execute(Sql::DeleteStaleLifecycle.get().as_str(), [])?;
+    Ok(())
+}
+
+fn event_row(e: &LifecycleEvent) -> Vec<String> {
+    vec![
+        e.event.clone(),
+        e.kind.clone(),
+        e.ref_date.clone(),
+        e.type_description.clone(),
+        e.brand.clone(),
+        e.model.clone(),
+        e.year.clone().unwrap_or_default(),
+    ]
+}
+
+pub fn show_lifecycle(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(from) = prompt_reference(conn, "From reference", 1)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let Some(to) = prompt_reference(conn, "To reference", 0)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let Some(tipo) = prompt_type(conn)? else {
+        return Ok(());
+    };
+    let by_brand = Confirm::new("Filter by brand?")
+        .with_default(false)
+        .prompt()?;
+    let brand_id = if by_brand {
+        match prompt_brand(conn, tipo.id)? {
+            Some(b) => Some(b.id),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
+    let output = prompt_output()?;
+
+    let events = select_lifecycle(conn, from.id, to.id, tipo.id, brand_id)?;
+    if events.is_empty() {
+        Label::NoLifecycleEvents.log();
+        return Ok(());
+    }
+    let rows: Vec<Vec<String>> = events.iter().map(event_row).collect();
+
+    match output {
+        OutputFormat::Terminal => {
+            (Label::LifecycleTitle {
+                from: &from.description,
+                to: &to.description,
+            })
+            .log();
+            print_table(&LIFECYCLE_HEADERS, &rows);
+        }
+        OutputFormat::Csv => {
+            write_csv("lifecycle", &LIFECYCLE_HEADERS, &rows)?;
+        }
+        OutputFormat::Json => {
+            write_json("lifecycle", &events)?;
+        }
+    }
+    Ok(())
+}
@@ -1,14 +1,32 @@
-use crate::config::select_rowcount;
-use crate::schema::{ ReferencesResponse, ModelsResponse, FipeStruct };
+use crate::config::{
+    calibrate_load_estimates,
+    select_count_sql,
+    update_status,
+    update_remote_reference,
+    reference_loaded,
+    remote_reference_code,
+    select_status,
+};
+use crate::schema::{ ReferencesResponse, ModelsResponse, FipeStruct, PriceResponse };
 use crate::selects::{
     select_brands,
     select_models,
     select_models_replicate,
+    select_models_by_reference,
+    select_brand_years,
+    select_prices,
     select_all_references,
     select_references,
     select_types,
 };
 use crate::label::{ Label };
+use crate::attributes::{ parse_model_attributes };
+use crate::aliases::{ alias_checkpoint, track_label, detect_fuzzy_aliases, report_new_aliases };
+use crate::lifecycle::{ refresh_lifecycle };
+use crate::anomalies::{ check_anomalies };
+use crate::watchlist::{ watchlist_alerts };
+use crate::webhooks::{ Event, deliver_webhooks };
+use crate::reloads::{ log_change, select_reload_rows };
 use crate::sql::{ Sql };
 use crate::utils::{
     throttle,
@@ -17,6 +35,10 @@ use crate::utils::{
     parse_ref_date,
     get_random_user_agent,
     parse_year,
+    parse_price_cents,
+    vehicle_type_slug,
+    estimate_rows,
+    rescale_progress,
 };
 use reqwest::Client;
 use rusqlite::{ params, Connection, Result };
@@ -25,16 +47,16 @@ use std::sync::OnceLock;
 
 static HTTP_CLIENT: OnceLock<Client> = OnceLock::new();
 
-fn get_client() -> &'static Client {
+pub fn get_client() -> &'static Client {
     HTTP_CLIENT.get_or_init(|| {
         Client::builder()
             .tcp_keepalive(std::time::Duration::from_secs(60))
             .build()
-            .expect(Label::ClientFail.to_string().as_str())
+            .unwrap_or_else(|_| panic!("{}", Label::ClientFail))
     })
 }
 
-async fn fetch_fipe(url: &str, body: &serde_json::Value) -> Option<reqwest::Response> {
+pub async fn fetch_fipe(url: &str, body: &serde_json::Value) -> Option<reqwest::Response> {
     let client = get_client();
     loop {
         match
@@ -62,6 +84,40 @@ async fn fetch_fipe(url: &str, body: &serde_json::Value) -> Option<reqwest::Resp
     }
 }
 
+// Rejected responses are kept with their request body so they can be audited and retried.
+pub fn log_failed_request(
+    conn: &Connection,
+    url: &str,
+    body: &serde_json::Value,
+    error: &str,
+    raw_value: Option<&str>
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.execute(
+        Sql::InsertFailedRequest.get().as_str(),
+        params![url, body.to_string(), error, raw_value]
+    )?;
+    Ok(())
+}
+
+// Queues load.failed and sends it right away; the caller decides whether to go on.
+pub async fn notify_load_failed(
+    conn: &Connection,
+    stage: &str,
+    message: &str
+) -> Result<(), Box<dyn std::error::Error>> {
+    (Event::LoadFailed { stage, message }).emit(conn)?;
+    deliver_webhooks(conn).await
+}
+
+// A load that can't go on: logs the error, notifies the webhooks and ends the program.
+async fn fail_load(conn: &Connection, stage: &str, message: &str) -> ! {
+    (Label::ResponseError { message }).log();
+    if let Err(e) = notify_load_failed(conn, stage, message).await {
+        (Label::WebhookError { message: &e.to_string() }).log();
+    }
+    exit(1);
+}
+
 pub async fn load_references(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     let response = Client::new()
         .post("https://veiculos.fipe.org.br/api/veiculos/ConsultarTabelaDeReferencia")
@@ -75,6 +131,9 @@ pub async fn load_references(conn: &Connection) -> Result<(), Box<dyn std::error
         .send().await?;
 
     let references_new: Vec<ReferencesResponse> = response.json().await?;
+    if let Some(latest) = references_new.iter().max_by_key(|r| r.codigo) {
+        update_remote_reference(conn, &remote_reference(latest))?;
+    }
     let references_old = select_all_references(conn)?;
     let len: u64 = references_new.len().try_into().unwrap();
     let pb = progress_bar(len);
@@ -105,7 +164,6 @@ pub async fn load_references(conn: &Connection) -> Result<(), Box<dyn std::error
                     }).to_string()
                 );
                 pb.inc(1);
-                ();
             }
 
             Err(rusqlite::Error::SqliteFailure(e, _)) if
@@ -120,20 +178,60 @@ pub async fn load_references(conn: &Connection) -> Result<(), Box<dyn std::error
             }
 
             Err(e) => {
-                let err_msg = e.to_string();
-                (Label::ResponseError { message: &err_msg }).log();
-                exit(1);
+                fail_load(conn, "References", &e.to_string()).await;
             }
         }
     }
     pb.finish_with_message((Label::LoadOk { entity: "References" }).to_string());
+    (Event::StageCompleted { stage: "References" }).emit(conn)?;
     Ok(())
 }
 
-pub async fn load_brands(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
-    let count: u64 = select_rowcount(conn)?.brands_rowcount.try_into().unwrap();
-    let pb = progress_bar(count);
+fn remote_reference(r: &ReferencesResponse) -> String {
+    format!("{} ({})", r.mes.trim(), r.codigo)
+}
+
+// Compares FIPE's newest reference with the local ones and updates the header status.
+pub async fn check_updates(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarTabelaDeReferencia";
+    let response = fetch_fipe(url, &serde_json::json!({})).await.unwrap();
+    let references_remote: Vec<ReferencesResponse> = response.json().await?;
+    let Some(latest) = references_remote.iter().max_by_key(|r| r.codigo) else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+    let reference = remote_reference(latest);
+    let codigo = latest.codigo.to_string();
+    // reference.new goes out once per FIPE reference, not on every check while it isn't loaded.
+    let previous = select_status(conn)?.latest_remote_reference;
+    let changed = previous.as_deref().and_then(remote_reference_code) != Some(codigo.as_str());
+    update_remote_reference(conn, &reference)?;
+
+    let references_local = select_all_references(conn)?;
+    if references_local.is_empty() {
+        update_status(conn, "empty")?;
+        (Label::NewReference { reference: &reference }).log();
+        if changed {
+            (Event::NewReference { reference: &reference }).emit(conn)?;
+        }
+        return Ok(());
+    }
 
+    if reference_loaded(conn, &codigo)? {
+        update_status(conn, "updated")?;
+        (Label::UpToDate { reference: &reference }).log();
+    } else {
+        update_status(conn, "outdated")?;
+        (Label::NewReference { reference: &reference }).log();
+        let listed = references_local.iter().any(|r| r.fipe.trim() == codigo);
+        if changed && !listed {
+            (Event::NewReference { reference: &reference }).emit(conn)?;
+        }
+    }
+    Ok(())
+}
+
+pub async fn load_brands(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     let types = match select_types(conn) {
         Ok(vt) => vt,
         Err(e) => {
@@ -148,12 +246,21 @@ pub async fn load_brands(conn: &Connection) -> Result<(), Box<dyn std::error::Er
         }
     };
 
-    if references.len() == 0 || types.len() == 0 {
+    if references.is_empty() || types.is_empty() {
         Label::NoResults.log();
         return Ok(());
     }
 
+    // One parent per reference × type.
+    let estimates = calibrate_load_estimates(conn)?;
+    let parents = references.len() * types.len();
+    let pb = progress_bar(estimate_rows(estimates.brands_per_parent, parents));
+    let mut parents_done = 0;
+
     let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarMarcas";
+    let checkpoint = alias_checkpoint(conn)?;
+    let mut descriptor_stmt = conn.prepare(Sql::InsertBrandDescriptor.get().as_str())?;
+    let mut lookup_stmt = conn.prepare(Sql::SelectBrandDescriptor.get().as_str())?;
     let mut stmt = conn.prepare(Sql::InsertBrand.get().as_str())?;
     for t in &types {
         for r in &references {
@@ -167,15 +274,27 @@ pub async fn load_brands(conn: &Connection) -> Result<(), Box<dyn std::error::Er
 
             let brands: Vec<FipeStruct> = match response.json().await {
                 Ok(data) => data,
-                Err(_) => {
+                Err(e) => {
+                    log_failed_request(conn, url, &body, &e.to_string(), None)?;
                     println!("Decode error. Skipping...");
                     continue;
                 }
             };
+            let mut created: Vec<(i32, String)> = Vec::new();
             for b in brands {
-                match stmt.execute(params![b.label, b.value, t.id, r.id]) {
+                let inserted = descriptor_stmt.execute(params![b.label, b.value, t.id])?;
+                let (descriptor_id, description): (i32, String) = lookup_stmt.query_row(
+                    params![b.value, t.id],
+                    |row| Ok((row.get(0)?, row.get(1)?))
+                )?;
+                if inserted == 0 {
+                    track_label(conn, "brand", descriptor_id, &description, &b.label, r.id)?;
+                } else {
+                    created.push((descriptor_id, b.label.clone()));
+                }
+                match stmt.execute(params![b.value, t.id, r.id]) {
                     Ok(_) => {
-                        let mes_ano = parse_ref_date(&r);
+                        let mes_ano = parse_ref_date(r);
                         pb.set_message(
                             (Label::InsertBrand {
                                 tipo: &t.description,
@@ -185,7 +304,6 @@ pub async fn load_brands(conn: &Connection) -> Result<(), Box<dyn std::error::Er
                             }).to_string()
                         );
                         pb.inc(1);
-                        ();
                     }
 
                     Err(rusqlite::Error::SqliteFailure(e, _)) if
@@ -201,35 +319,50 @@ pub async fn load_brands(conn: &Connection) -> Result<(), Box<dyn std::error::Er
                         return Ok(());
                     }
                     Err(e) => {
-                        let err_msg = e.to_string();
-                        pb.set_message((Label::ResponseError { message: &err_msg }).to_string());
-                        exit(1);
+                        fail_load(conn, "Brands", &e.to_string()).await;
                     }
                 };
             }
+            detect_fuzzy_aliases(conn, "brand", &created, r.id)?;
+            parents_done += 1;
+            rescale_progress(
+                &pb,
+                parents_done,
+                parents - parents_done,
+                estimates.brands_per_parent
+            );
             throttle().await;
         }
     }
+    calibrate_load_estimates(conn)?;
     pb.finish_with_message((Label::LoadOk { entity: "Brands" }).to_string());
+    (Event::StageCompleted { stage: "Brands" }).emit(conn)?;
+    report_new_aliases(conn, checkpoint)?;
     Ok(())
 }
 
 pub async fn load_models(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
-    let count: u64 = select_rowcount(conn)?.models_rowcount.try_into().unwrap();
-    let pb = progress_bar(count);
-
     let brands = match select_brands(conn) {
         Ok(vb) => vb,
         Err(e) => {
             panic!("{:?}", e);
         }
     };
-    if brands.len() == 0 {
+    if brands.is_empty() {
         (Label::LoadOk { entity: "Models" }).log();
+        (Event::StageCompleted { stage: "Models" }).emit(conn)?;
         return Ok(());
     }
+    // One parent per brand per reference.
+    let estimates = calibrate_load_estimates(conn)?;
+    let pb = progress_bar(estimate_rows(estimates.models_per_parent, brands.len()));
+    let mut parents_done = 0;
     let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarModelos";
+    let checkpoint = alias_checkpoint(conn)?;
+    let mut descriptor_stmt = conn.prepare(Sql::InsertModelDescriptor.get().as_str())?;
+    let mut lookup_stmt = conn.prepare(Sql::SelectModelDescriptor.get().as_str())?;
     let mut stmt = conn.prepare(Sql::InsertModel.get().as_str())?;
+    let mut brand_year_stmt = conn.prepare(Sql::InsertBrandYear.get().as_str())?;
     for b in &brands {
         let body =
             serde_json::json!({
@@ -237,17 +370,46 @@ pub async fn load_models(conn: &Connection) -> Result<(), Box<dyn std::error::Er
             "codigoTabelaReferencia": b.ref_id,
             "codigoMarca": b.fipe
         });
-        let response = fetch_fipe(&url, &body).await.unwrap();
+        let response = fetch_fipe(url, &body).await.unwrap();
 
         let models: ModelsResponse = match response.json().await {
             Ok(data) => data,
-            Err(_) => {
+            Err(e) => {
+                log_failed_request(conn, url, &body, &e.to_string(), None)?;
                 println!("Decode error. Skipping...");
                 continue;
             }
         };
+        for y in &models.years {
+            brand_year_stmt.execute(params![b.id, y.value, y.label])?;
+        }
+        let mut created: Vec<(i32, String)> = Vec::new();
         for m in models.model {
-            match stmt.execute(params![m.label, m.value, b.id]) {
+            let a = parse_model_attributes(&m.label);
+            let inserted = descriptor_stmt.execute(
+                params![
+                    m.label,
+                    m.value,
+                    b.id,
+                    a.engine_displacement,
+                    a.valves,
+                    a.doors,
+                    a.transmission,
+                    a.turbo,
+                    a.body_style,
+                    a.fuel
+                ]
+            )?;
+            let (descriptor_id, description): (i32, String) = lookup_stmt.query_row(
+                params![m.value, b.id],
+                |row| Ok((row.get(0)?, row.get(1)?))
+            )?;
+            if inserted == 0 {
+                track_label(conn, "model", descriptor_id, &description, &m.label, b.ref_id)?;
+            } else {
+                created.push((descriptor_id, m.label.clone()));
+            }
+            match stmt.execute(params![m.value, b.id]) {
                 Ok(_) => {
                     pb.inc(1);
                     pb.set_message(
@@ -259,7 +421,6 @@ pub async fn load_models(conn: &Connection) -> Result<(), Box<dyn std::error::Er
                             codigo: &m.value.to_string(),
                         }).to_string()
                     );
-                    ();
                 }
 
                 Err(rusqlite::Error::SqliteFailure(e, _)) if
@@ -275,33 +436,49 @@ pub async fn load_models(conn: &Connection) -> Result<(), Box<dyn std::error::Er
                     return Ok(());
                 }
                 Err(e) => {
-                    let err_msg = e.to_string();
-                    (Label::ResponseError { message: &err_msg }).log();
-                    exit(1);
+                    fail_load(conn, "Models", &e.to_string()).await;
                 }
             }
         }
+        detect_fuzzy_aliases(conn, "model", &created, b.ref_id)?;
+        parents_done += 1;
+        rescale_progress(
+            &pb,
+            parents_done,
+            brands.len() - parents_done,
+            estimates.models_per_parent
+        );
         throttle().await;
     }
+    conn.execute(Sql::RefreshModelLifecycle.get().as_str(), [])?;
+    calibrate_load_estimates(conn)?;
     pb.finish_with_message((Label::LoadOk { entity: "Models" }).to_string());
+    (Event::StageCompleted { stage: "Models" }).emit(conn)?;
+    report_new_aliases(conn, checkpoint)?;
     Ok(())
 }
 
 pub async fn load_years(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
-    let count: u64 = select_rowcount(conn)?.years_rowcount.try_into().unwrap();
-    let pb = progress_bar(count);
-
     let models = match select_models(conn) {
         Ok(vm) => vm,
         Err(e) => {
             panic!("{:?}", e);
         }
     };
-    if models.len() == 0 {
+    if models.is_empty() {
         (Label::LoadOk { entity: "Years" }).log();
+        (Event::StageCompleted { stage: "Years" }).emit(conn)?;
         return Ok(());
     }
+    // One parent per model per reference; each request fills every pending reference of a model.
+    let estimates = calibrate_load_estimates(conn)?;
+    let parents: usize = select_count_sql(conn, Sql::SelectPendingModelRefsCount)?.count
+        .try_into()
+        .unwrap_or(0);
+    let pb = progress_bar(estimate_rows(estimates.years_per_parent, parents));
+    let mut parents_done = 0;
     let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarAnoModelo";
+    let mut descriptor_stmt = conn.prepare(Sql::InsertVehicle.get().as_str())?;
     let mut stmt = conn.prepare(Sql::InsertYear.get().as_str())?;
     for m in &models {
         let body =
@@ -311,21 +488,36 @@ pub async fn load_years(conn: &Connection) -> Result<(), Box<dyn std::error::Err
             "codigoMarca": &m.brand_id,
             "codigoModelo": &m.fipe
         });
-        let response = fetch_fipe(&url, &body).await.unwrap();
+        let response = fetch_fipe(url, &body).await.unwrap();
 
         let years: Vec<FipeStruct> = match response.json().await {
             Ok(data) => data,
-            Err(_) => {
+            Err(e) => {
+                log_failed_request(conn, url, &body, &e.to_string(), None)?;
                 println!("Decode error. Skipping...");
                 continue;
             }
         };
 
-        let models_replica = select_models_replicate(conn, &m.fipe)?;
+        let models_replica = select_models_replicate(conn, m.model_id)?;
         for y in years {
-            let (year_date, fuel_id) = parse_year(&y.value);
+            let Some(year) = parse_year(&y.value) else {
+                log_failed_request(conn, url, &body, "invalid year code", Some(&y.value))?;
+                continue;
+            };
             for mr in &models_replica {
-                match stmt.execute(params![y.label, year_date, y.value, mr.id, fuel_id]) {
+                descriptor_stmt.execute(
+                    params![
+                        y.label,
+                        year.value,
+                        y.value,
+                        mr.id,
+                        year.fuel_id,
+                        year.model_year,
+                        year.is_zero_km
+                    ]
+                )?;
+                match stmt.execute(params![y.value, mr.id]) {
                     Ok(_) => {
                         pb.inc(1);
                         pb.set_message(
@@ -337,7 +529,6 @@ pub async fn load_years(conn: &Connection) -> Result<(), Box<dyn std::error::Err
                                 ano: &y.label,
                             }).to_string()
                         );
-                        ();
                     }
 
                     Err(rusqlite::Error::SqliteFailure(e, _)) if
@@ -353,15 +544,320 @@ pub async fn load_years(conn: &Connection) -> Result<(), Box<dyn std::error::Err
                         return Ok(());
                     }
                     Err(e) => {
-                        let err_msg = e.to_string();
-                        (Label::ResponseError { message: &err_msg }).log();
-                        exit(1);
+                        fail_load(conn, "Years", &e.to_string()).await;
                     }
                 };
             }
         }
+        conn.execute(Sql::UpdateYearsVerified.get().as_str(), [m.id])?;
+        parents_done += models_replica.len();
+        rescale_progress(
+            &pb,
+            parents_done,
+            parents.saturating_sub(parents_done),
+            estimates.years_per_parent
+        );
+        throttle().await;
+    }
+    conn.execute(Sql::RefreshVehicleLifecycle.get().as_str(), [])?;
+    calibrate_load_estimates(conn)?;
+    pb.finish_with_message((Label::LoadOk { entity: "Years" }).to_string());
+    (Event::StageCompleted { stage: "Years" }).emit(conn)?;
+    Ok(())
+}
+
+// Accurate mode: every model reference gets the years listed in its own reference table.
+// Rows replicated from a later reference that the older table doesn't list are removed,
+// each logged in reload_log first.
+pub async fn load_years_by_reference(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let models = select_models_by_reference(conn)?;
+    if models.is_empty() {
+        (Label::LoadOk { entity: "Years" }).log();
+        (Event::StageCompleted { stage: "Years" }).emit(conn)?;
+        return Ok(());
+    }
+    let estimates = calibrate_load_estimates(conn)?;
+    let parents: usize = select_count_sql(conn, Sql::SelectUnverifiedModelRefsCount)?.count
+        .try_into()
+        .unwrap_or(0);
+    let pb = progress_bar(estimate_rows(estimates.years_per_parent, parents));
+    let mut parents_done = 0;
+    let mut added = 0;
+    let mut removed = 0;
+    let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarAnoModelo";
+    let mut descriptor_stmt = conn.prepare(Sql::InsertVehicle.get().as_str())?;
+    let mut stmt = conn.prepare(Sql::InsertYear.get().as_str())?;
+    let mut delete_stmt = conn.prepare(Sql::DeleteVehicleReference.get().as_str())?;
+    for m in &models {
+        let body =
+            serde_json::json!({
+            "codigoTipoVeiculo": &m.type_id,
+            "codigoTabelaReferencia": &m.ref_id,
+            "codigoMarca": &m.brand_id,
+            "codigoModelo": &m.fipe
+        });
+        let response = fetch_fipe(url, &body).await.unwrap();
+
+        let years: Vec<FipeStruct> = match response.json().await {
+            Ok(data) => data,
+            Err(e) => {
+                log_failed_request(conn, url, &body, &e.to_string(), None)?;
+                println!("Decode error. Skipping...");
+                continue;
+            }
+        };
+
+        let tx = conn.unchecked_transaction()?;
+        let ref_id: i32 = conn.query_row(
+            Sql::SelectModelRefKeys.get().as_str(),
+            [m.id],
+            |row| row.get("ref_id")
+        )?;
+        let existing = select_reload_rows(conn, Sql::SelectReloadYears, [m.id])?;
+        for y in &years {
+            if existing.iter().any(|e| e.fipe == y.value) {
+                continue;
+            }
+            let Some(year) = parse_year(&y.value) else {
+                log_failed_request(conn, url, &body, "invalid year code", Some(&y.value))?;
+                continue;
+            };
+            descriptor_stmt.execute(
+                params![
+                    y.label,
+                    year.value,
+                    y.value,
+                    m.id,
+                    year.fuel_id,
+                    year.model_year,
+                    year.is_zero_km
+                ]
+            )?;
+            stmt.execute(params![y.value, m.id])?;
+            added += 1;
+            pb.inc(1);
+            pb.set_message(
+                (Label::InsertYear {
+                    tipo: &m.type_description,
+                    referencia: &m.ref_date,
+                    marca: &m.brand_description,
+                    modelo: &m.description,
+                    ano: &y.label,
+                }).to_string()
+            );
+        }
+        for e in &existing {
+            if !years.iter().any(|y| y.value == e.fipe) {
+                log_change(conn, "year", "removed", e.id, &e.description, ref_id)?;
+                delete_stmt.execute([e.id])?;
+                removed += 1;
+            }
+        }
+        conn.execute(Sql::UpdateYearsVerified.get().as_str(), [m.id])?;
+        tx.commit()?;
+
+        parents_done += 1;
+        rescale_progress(
+            &pb,
+            parents_done,
+            parents.saturating_sub(parents_done),
+            estimates.years_per_parent
+        );
         throttle().await;
     }
+    refresh_lifecycle(conn)?;
+    calibrate_load_estimates(conn)?;
     pb.finish_with_message((Label::LoadOk { entity: "Years" }).to_string());
+    (Event::StageCompleted { stage: "Years" }).emit(conn)?;
+    (Label::YearsCorrected {
+        added: &added.to_string(),
+        removed: &removed.to_string(),
+    }).log();
+    Ok(())
+}
+
+// One request per brand, reference and model year (ConsultarModelosAtravesDoAno) instead of
+// one per model; uses the year list captured from ConsultarModelos while loading models.
+pub async fn load_years_by_brand(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let brand_years = select_brand_years(conn)?;
+    if brand_years.is_empty() {
+        (Label::LoadOk { entity: "Years" }).log();
+        (Event::StageCompleted { stage: "Years" }).emit(conn)?;
+        return Ok(());
+    }
+    let pb = progress_bar(brand_years.len().try_into().unwrap_or(0));
+    let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarModelosAtravesDoAno";
+    let mut model_ref_stmt = conn.prepare(Sql::SelectModelRefByFipe.get().as_str())?;
+    let mut descriptor_stmt = conn.prepare(Sql::InsertVehicle.get().as_str())?;
+    let mut stmt = conn.prepare(Sql::InsertYear.get().as_str())?;
+    for y in &brand_years {
+        let Some(year) = parse_year(&y.fipe) else {
+            let body =
+                serde_json::json!({
+                "codigoTabelaReferencia": &y.ref_id,
+                "codigoMarca": &y.brand_id,
+                "ano": &y.fipe
+            });
+            log_failed_request(conn, url, &body, "invalid year code", Some(&y.fipe))?;
+            conn.execute(Sql::UpdateBrandYearLoaded.get().as_str(), [y.id])?;
+            pb.inc(1);
+            continue;
+        };
+        let body =
+            serde_json::json!({
+            "codigoTipoVeiculo": y.type_id,
+            "codigoTabelaReferencia": &y.ref_id,
+            "codigoMarca": &y.brand_id,
+            "ano": &y.fipe,
+            "codigoTipoCombustivel": &year.fuel_id,
+            "anoModelo": year.model_year
+        });
+        let response = fetch_fipe(url, &body).await.unwrap();
+
+        let models: Vec<FipeStruct> = match response.json().await {
+            Ok(data) => data,
+            Err(e) => {
+                log_failed_request(conn, url, &body, &e.to_string(), None)?;
+                println!("Decode error. Skipping...");
+                continue;
+            }
+        };
+
+        for m in &models {
+            let model_ref_id: i32 = match
+                model_ref_stmt.query_row(params![y.brand_ref_id, m.value], |row| row.get(0))
+            {
+                Ok(id) => id,
+                // Model not loaded for this brand and reference yet.
+                Err(rusqlite::Error::QueryReturnedNoRows) => {
+                    continue;
+                }
+                Err(e) => {
+                    return Err(Box::new(e));
+                }
+            };
+            descriptor_stmt.execute(
+                params![
+                    y.description,
+                    year.value,
+                    y.fipe,
+                    model_ref_id,
+                    year.fuel_id,
+                    year.model_year,
+                    year.is_zero_km
+                ]
+            )?;
+            match stmt.execute(params![y.fipe, model_ref_id]) {
+                Ok(_) => {
+                    pb.set_message(
+                        (Label::InsertYear {
+                            tipo: &y.type_description,
+                            referencia: &y.ref_date,
+                            marca: &y.brand_description,
+                            modelo: &m.label,
+                            ano: &y.description,
+                        }).to_string()
+                    );
+                }
+
+                // Already filled by another years load.
+                Err(rusqlite::Error::SqliteFailure(e, _)) if
+                    e.code == rusqlite::ErrorCode::ConstraintViolation
+                => {}
+
+                Err(e) => {
+                    fail_load(conn, "Years", &e.to_string()).await;
+                }
+            }
+        }
+        conn.execute(Sql::UpdateBrandYearLoaded.get().as_str(), [y.id])?;
+        conn.execute(Sql::UpdateBrandYearsVerified.get().as_str(), [y.brand_ref_id])?;
+        pb.inc(1);
+        throttle().await;
+    }
+    refresh_lifecycle(conn)?;
+    calibrate_load_estimates(conn)?;
+    pb.finish_with_message((Label::LoadOk { entity: "Years" }).to_string());
+    (Event::StageCompleted { stage: "Years" }).emit(conn)?;
+    Ok(())
+}
+
+pub async fn load_prices(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let count: u64 = select_count_sql(conn, Sql::SelectPricesCount)?.count.try_into().unwrap_or(0);
+    let pb = progress_bar(count);
+
+    let prices = match select_prices(conn) {
+        Ok(vp) => vp,
+        Err(e) => {
+            panic!("{:?}", e);
+        }
+    };
+    if prices.is_empty() {
+        (Label::LoadOk { entity: "Prices" }).log();
+        (Event::StageCompleted { stage: "Prices" }).emit(conn)?;
+        return Ok(());
+    }
+    let url = "https://veiculos.fipe.org.br/api/veiculos/ConsultarValorComTodosParametros";
+    let mut stmt = conn.prepare(Sql::UpdatePrice.get().as_str())?;
+    let mut code_stmt = conn.prepare(Sql::UpdateFipeCode.get().as_str())?;
+    for p in &prices {
+        let body =
+            serde_json::json!({
+            "codigoTipoVeiculo": p.type_id,
+            "codigoTabelaReferencia": p.ref_id,
+            "codigoMarca": p.brand_id,
+            "codigoModelo": p.model_id,
+            "anoModelo": p.model_year,
+            "codigoTipoCombustivel": p.fuel_id,
+            "tipoVeiculo": vehicle_type_slug(p.type_id),
+            "modeloCodigoExterno": "",
+            "tipoConsulta": "tradicional"
+        });
+        let response = fetch_fipe(url, &body).await.unwrap();
+
+        let price: PriceResponse = match response.json().await {
+            Ok(data) => data,
+            Err(e) => {
+                log_failed_request(conn, url, &body, &e.to_string(), None)?;
+                println!("Decode error. Skipping...");
+                continue;
+            }
+        };
+
+        let Some(cents) = parse_price_cents(&price.valor) else {
+            log_failed_request(conn, url, &body, "malformed price", Some(&price.valor))?;
+            (Label::MalformedPrice { valor: &price.valor }).log();
+            continue;
+        };
+        match stmt.execute(params![cents, price.valor, p.id]) {
+            Ok(_) => {
+                code_stmt.execute(params![price.codigo_fipe, p.id])?;
+                pb.inc(1);
+                pb.set_message(
+                    (Label::InsertPrice {
+                        tipo: &p.type_description,
+                        referencia: &p.ref_date,
+                        marca: &p.brand_description,
+                        modelo: &p.model_description,
+                        ano: &p.description,
+                        valor: &price.valor,
+                    }).to_string()
+                );
+            }
+
+            Err(rusqlite::Error::SqliteFailure(_, Some(msg))) if msg.contains("no such table") => {
+                Label::TableNotExist.log();
+                return Ok(());
+            }
+            Err(e) => {
+                fail_load(conn, "Prices", &e.to_string()).await;
+            }
+        }
+        throttle().await;
+    }
+    pb.finish_with_message((Label::LoadOk { entity: "Prices" }).to_string());
+    (Event::StageCompleted { stage: "Prices" }).emit(conn)?;
+    check_anomalies(conn)?;
+    watchlist_alerts(conn)?;
     Ok(())
 }
@@ -0,0 +1,193 @@
+use crate::label::Label;
+use crate::loads::{fetch_fipe, log_failed_request};
+use crate::prompts::{prompt_fuel, prompt_reference};
+use crate::schema::{PriceLookup, PriceResponse};
+use crate::selects::{ambiguous_code, select_types};
+use crate::sql::Sql;
+use crate::utils::{format_price, parse_price_cents, print_table, vehicle_type_slug};
+use inquire::{CustomType, Text};
+use rusqlite::{params, Connection, OptionalExtension};
+
+const PRICE_URL: &str =
+    "https://veiculos.fipe.org.br/api/veiculos/ConsultarValorComTodosParametros";
+
+// Price of a FIPE code / model year / fuel in one reference (the latest when `ref_id` is None).
+// Loaded prices are used first, then earlier lookups; otherwise FIPE is asked and the answer
+// is stored on the loaded vehicle, or in `price_lookups` when the vehicle isn't loaded.
+pub async fn lookup_price(
+    conn: &Connection,
+    fipe_code: &str,
+    model_year: i32,
+    fuel_id: i32,
+    ref_id: Option<i32>,
+) -> Result<Option<PriceLookup>, Box<dyn std::error::Error>> {
+    let ref_id = match ref_id {
+        Some(id) => id,
+        None => match conn
+            .query_row(Sql::SelectLatestReference.get().as_str(), [], |row| {
+                row.get(0)
+            })
+            .optional()?
+        {
+            Some(id) => id,
+            None => return Ok(None),
+        },
+    };
+    let key = params![fipe_code, model_year, fuel_id, ref_id];
+
+    let mut stmt = conn.prepare(Sql::SelectLocalPriceByCode.get().as_str())?;
+    let mut matches = stmt
+        .query_map(key, |row| {
+            Ok((
+                row.get("id")?,
+                row.get("price")?,
+                row.get("brand")?,
+                row.get("model")?,
+            ))
+        })?
+        .collect::<Result<Vec<(i32, Option<f64>, String, String)>, rusqlite::Error>>()?;
+    if matches.len() > 1 {
+        return Err(ambiguous_code(fipe_code, model_year, matches.len()));
+    }
+    let local = matches.pop();
+    if let Some((_, Some(price), brand, model)) = &local {
+        return Ok(Some(PriceLookup {
+            brand: brand.clone(),
+            model: model.clone(),
+            price: *price,
+            source: "database".to_string(),
+        }));
+    }
+
+    let cached: Option<PriceLookup> = conn
+        .query_row(Sql::SelectCachedPrice.get().as_str(), key, |row| {
+            Ok(PriceLookup {
+                brand: row.get("brand")?,
+                model: row.get("model")?,
+                price: row.get("price")?,
+                source: "cache".to_string(),
+            })
+        })
+        .optional()?;
+    if cached.is_some() {
+        return Ok(cached);
+    }
+
+    // FIPE needs the vehicle type; use the one the code is loaded under, else try each.
+    let mut stmt = conn.prepare(Sql::SelectCodeTypes.get().as_str())?;
+    let mut type_ids = stmt
+        .query_map([fipe_code], |row| row.get(0))?
+        .collect::<Result<Vec<i32>, rusqlite::Error>>()?;
+    if type_ids.is_empty() {
+        type_ids = select_types(conn)?.iter().map(|t| t.id).collect();
+    }
+    let ref_fipe: String =
+        conn.query_row(Sql::SelectReferenceFipe.get().as_str(), [ref_id], |row| {
+            row.get(0)
+        })?;
+
+    for type_id in type_ids {
+        let body = serde_json::json!({
+            "codigoTipoVeiculo": type_id,
+            "codigoTabelaReferencia": ref_fipe,
+            "codigoMarca": "",
+            "codigoModelo": "",
+            "anoModelo": model_year,
+            "codigoTipoCombustivel": fuel_id,
+            "tipoVeiculo": vehicle_type_slug(type_id),
+            "modeloCodigoExterno": fipe_code,
+            "tipoConsulta": "codigo"
+        });
+        let response = fetch_fipe(PRICE_URL, &body).await.unwrap();
+        // Unknown codes come back as {"codigo": ..., "erro": ...}.
+        let Ok(fetched) = response.json::<PriceResponse>().await else {
+            continue;
+        };
+        let Some(cents) = parse_price_cents(&fetched.valor) else {
+            log_failed_request(
+                conn,
+                PRICE_URL,
+                &body,
+                "malformed price",
+                Some(&fetched.valor),
+            )?;
+            (Label::MalformedPrice {
+                valor: &fetched.valor,
+            })
+            .log();
+            return Ok(None);
+        };
+
+        match &local {
+            Some((vehicle_ref_id, _, _, _)) => {
+                conn.execute(
+                    Sql::UpdatePrice.get().as_str(),
+                    params![cents, fetched.valor, vehicle_ref_id],
+                )?;
+            }
+            None => {
+                conn.execute(
+                    Sql::InsertPriceLookup.get().as_str(),
+                    params![
+                        fipe_code,
+                        model_year,
+                        fuel_id,
+                        ref_id,
+                        fetched.marca,
+                        fetched.modelo,
+                        cents,
+                        fetched.valor
+                    ],
+                )?;
+            }
+        }
+        return Ok(Some(PriceLookup {
+            brand: fetched.marca,
+            model: fetched.modelo,
+            price: cents as f64 / 100.0,
+            source: "fipe".to_string(),
+        }));
+    }
+    Ok(None)
+}
+
+pub async fn show_price_lookup(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let code = Text::new("FIPE code (e.g. 005340-6):").prompt()?;
+    let year = CustomType::<i32>::new("Model year (32000 for zero km):").prompt()?;
+    let Some(fuel) = prompt_fuel(conn)? else {
+        return Ok(());
+    };
+    let Some(reference) = prompt_reference(conn, "Reference", 0)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+
+    let code = code.trim();
+    let Some(lookup) = lookup_price(conn, code, year, fuel.id, Some(reference.id)).await? else {
+        Label::PriceNotFound.log();
+        return Ok(());
+    };
+    print_table(
+        &[
+            "Reference",
+            "Code",
+            "Year",
+            "Fuel",
+            "Brand",
+            "Model",
+            "Price",
+            "Source",
+        ],
+        &[vec![
+            reference.description.clone(),
+            code.to_string(),
+            year.to_string(),
+            fuel.description.clone(),
+            lookup.brand,
+            lookup.model,
+            format_price(lookup.price),
+            lookup.source,
+        ]],
+    );
+    Ok(())
+}
@@ -6,12 +6,56 @@ mod config;
 mod sql;
 mod label;
 mod menu;
+mod prompts;
+mod history;
+mod export;
+mod diff;
+mod depreciation;
+mod search;
+mod attributes;
+mod aliases;
+mod lifecycle;
+mod deletes;
+mod reloads;
+mod lookup;
+mod fleet;
+mod index;
+mod inflation;
+mod anomalies;
+mod watchlist;
+mod webhooks;
 
-use loads::{ load_brands, load_models, load_references, load_years };
+use loads::{
+    load_brands,
+    load_models,
+    load_references,
+    load_years,
+    load_years_by_reference,
+    load_years_by_brand,
+    load_prices,
+    notify_load_failed,
+    check_updates,
+};
 use label::{ Label };
-use menu::{ MainMenu, MaintMenu, LoadMenu };
+use menu::{ MainMenu, MaintMenu, LoadMenu, ReportMenu };
+use history::show_price_history;
+use diff::show_reference_diff;
+use depreciation::show_depreciation;
+use search::show_search;
+use lifecycle::show_lifecycle;
+use attributes::backfill_attributes;
+use aliases::review_aliases;
+use deletes::{ delete_reference, delete_brand_reference };
+use reloads::reload;
+use lookup::show_price_lookup;
+use fleet::show_fleet_valuation;
+use index::show_price_index;
+use inflation::import_inflation;
+use anomalies::show_anomalies;
+use watchlist::show_watchlist;
+use webhooks::{ configure_webhooks, deliver_webhooks };
 use utils::{ clear_screen, press_key_continue };
-use config::{ setup_db, check_db, update_status, select_status };
+use config::{ setup_db, check_db, update_status, select_status, migrate_db, enable_foreign_keys };
 use rusqlite::{ Connection, Result };
 use owo_colors::OwoColorize;
 use inquire::Select;
@@ -20,6 +64,8 @@ use inquire::ui::{ RenderConfig, Styled, Color };
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let conn = Connection::open("fipe_rs.db")?;
+    migrate_db(&conn)?;
+    enable_foreign_keys(&conn)?;
 
     loop {
         clear_screen();
@@ -31,10 +77,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             _ => "Outdated".bright_red().blink_fast().to_string(),
         };
         let last_update = config.last_update.unwrap_or_else(|| "Never".to_string());
+        let latest_remote = config.latest_remote_reference.unwrap_or_else(|| "Not checked".to_string());
 
-        (Label::Header { db_status: &db_status, last_update: &last_update }).log();
+        (Label::Header { db_status: &db_status, last_update: &last_update, latest_remote: &latest_remote }).log();
 
-        let options = vec![MainMenu::Loads, MainMenu::Maintenance, MainMenu::Exit];
+        let options = vec![MainMenu::Loads, MainMenu::Reports, MainMenu::Maintenance, MainMenu::Exit];
         let render_config = RenderConfig::default()
             .with_prompt_prefix(Styled::new(""))
             .with_highlighted_option_prefix(Styled::new("> ").with_fg(Color::LightGreen));
@@ -42,6 +89,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         match main_ans {
             Ok(MainMenu::Loads) => run_loads(&conn).await?,
+            Ok(MainMenu::Reports) => run_reports(&conn).await?,
             Ok(MainMenu::Maintenance) => run_maint(&conn).await?,
             Ok(MainMenu::Exit) | Err(_) => {
                 break;
@@ -59,20 +107,74 @@ async fn run_loads(conn: &Connection) -> Result<(), Box<dyn std::error::Error>>
             LoadMenu::LoadBrands,
             LoadMenu::LoadModels,
             LoadMenu::LoadYears,
+            LoadMenu::LoadYearsByReference,
+            LoadMenu::LoadYearsByBrand,
+            LoadMenu::LoadPrices,
+            LoadMenu::Reload,
             LoadMenu::Back
         ];
         let render_config = RenderConfig::default()
             .with_prompt_prefix(Styled::new(""))
             .with_highlighted_option_prefix(Styled::new("> ").with_fg(Color::LightGreen));
         let ans = Select::new("Loads", options).with_render_config(render_config).prompt()?;
-        match ans {
-            LoadMenu::LoadRefs => load_references(conn).await?,
-            LoadMenu::LoadBrands => load_brands(conn).await?,
-            LoadMenu::LoadModels => load_models(conn).await?,
-            LoadMenu::LoadYears => load_years(conn).await?,
+        let (stage, result) = match ans {
+            LoadMenu::LoadRefs => ("References", load_references(conn).await),
+            LoadMenu::LoadBrands => ("Brands", load_brands(conn).await),
+            LoadMenu::LoadModels => ("Models", load_models(conn).await),
+            LoadMenu::LoadYears => ("Years", load_years(conn).await),
+            LoadMenu::LoadYearsByReference => ("Years", load_years_by_reference(conn).await),
+            LoadMenu::LoadYearsByBrand => ("Years", load_years_by_brand(conn).await),
+            LoadMenu::LoadPrices => ("Prices", load_prices(conn).await),
+            LoadMenu::Reload => ("Reload", reload(conn).await),
             LoadMenu::Back => {
                 break;
             }
+        };
+        // Errors that leave a load early (database, decoding) are reported like a failed response.
+        if let Err(e) = result {
+            notify_load_failed(conn, stage, &e.to_string()).await?;
+            return Err(e);
+        }
+        deliver_webhooks(conn).await?;
+        press_key_continue();
+    }
+    Ok(())
+}
+
+async fn run_reports(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    loop {
+        let options = vec![
+            ReportMenu::PriceHistory,
+            ReportMenu::ReferenceDiff,
+            ReportMenu::Depreciation,
+            ReportMenu::Search,
+            ReportMenu::Lifecycle,
+            ReportMenu::PriceLookup,
+            ReportMenu::FleetValuation,
+            ReportMenu::PriceIndex,
+            ReportMenu::Anomalies,
+            ReportMenu::Watchlist,
+            ReportMenu::Back
+        ];
+        let render_config = RenderConfig::default()
+            .with_prompt_prefix(Styled::new(""))
+            .with_highlighted_option_prefix(Styled::new("> ").with_fg(Color::LightGreen));
+        let ans = Select::new("Reports", options).with_render_config(render_config).prompt()?;
+
+        match ans {
+            ReportMenu::PriceHistory => show_price_history(conn)?,
+            ReportMenu::ReferenceDiff => show_reference_diff(conn)?,
+            ReportMenu::Depreciation => show_depreciation(conn)?,
+            ReportMenu::Search => show_search(conn)?,
+            ReportMenu::Lifecycle => show_lifecycle(conn)?,
+            ReportMenu::PriceLookup => show_price_lookup(conn).await?,
+            ReportMenu::FleetValuation => show_fleet_valuation(conn)?,
+            ReportMenu::PriceIndex => show_price_index(conn)?,
+            ReportMenu::Anomalies => show_anomalies(conn)?,
+            ReportMenu::Watchlist => show_watchlist(conn).await?,
+            ReportMenu::Back => {
+                break;
+            }
         }
         press_key_continue();
     }
@@ -81,7 +183,18 @@ async fn run_loads(conn: &Connection) -> Result<(), Box<dyn std::error::Error>>
 
 async fn run_maint(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
     loop {
-        let options = vec![MaintMenu::RecreateDatabase, MaintMenu::CheckUpdates, MaintMenu::Back];
+        let options = vec![
+            MaintMenu::RecreateDatabase,
+            MaintMenu::CheckUpdates,
+            MaintMenu::CheckDatabase,
+            MaintMenu::BackfillAttributes,
+            MaintMenu::ReviewAliases,
+            MaintMenu::ImportInflation,
+            MaintMenu::Webhooks,
+            MaintMenu::DeleteReference,
+            MaintMenu::DeleteBrand,
+            MaintMenu::Back
+        ];
         let render_config = RenderConfig::default()
             .with_prompt_prefix(Styled::new(""))
             .with_highlighted_option_prefix(Styled::new("> ").with_fg(Color::LightGreen));
@@ -92,11 +205,19 @@ async fn run_maint(conn: &Connection) -> Result<(), Box<dyn std::error::Error>>
                 setup_db(conn)?;
                 update_status(conn, "empty")?;
             }
-            MaintMenu::CheckUpdates => check_db(conn)?,
+            MaintMenu::CheckUpdates => check_updates(conn).await?,
+            MaintMenu::CheckDatabase => check_db(conn)?,
+            MaintMenu::BackfillAttributes => backfill_attributes(conn)?,
+            MaintMenu::ReviewAliases => review_aliases(conn)?,
+            MaintMenu::ImportInflation => import_inflation(conn)?,
+            MaintMenu::Webhooks => configure_webhooks(conn)?,
+            MaintMenu::DeleteReference => delete_reference(conn)?,
+            MaintMenu::DeleteBrand => delete_brand_reference(conn)?,
             MaintMenu::Back => {
                 break;
             }
         }
+        deliver_webhooks(conn).await?;
         press_key_continue();
     }
     Ok(())
@@ -2,12 +2,20 @@ use std::fmt;
 
 pub enum MainMenu {
     Loads,
+    Reports,
     Maintenance,
     Exit,
 }
 pub enum MaintMenu {
     RecreateDatabase,
     CheckUpdates,
+    CheckDatabase,
+    BackfillAttributes,
+    ReviewAliases,
+    ImportInflation,
+    Webhooks,
+    DeleteReference,
+    DeleteBrand,
     Back,
 }
 
@@ -16,13 +24,86 @@ pub enum LoadMenu {
     LoadBrands,
     LoadModels,
     LoadYears,
+    LoadYearsByReference,
+    LoadYearsByBrand,
+    LoadPrices,
+    Reload,
     Back,
 }
 
+pub enum ReportMenu {
+    PriceHistory,
+    ReferenceDiff,
+    Depreciation,
+    Search,
+    Lifecycle,
+    PriceLookup,
+    FleetValuation,
+    PriceIndex,
+    Anomalies,
+    Watchlist,
+    Back,
+}
+
+pub enum WatchMenu {
+    Show,
+    CheckAlerts,
+    Add,
+    Remove,
+    Settings,
+    Back,
+}
+
+pub enum WebhookMenu {
+    List,
+    Add,
+    Remove,
+    Back,
+}
+
+pub enum DepreciationScope {
+    Type,
+    Brand,
+    Model,
+}
+
+pub enum IndexScope {
+    Market,
+    Type,
+    Brand,
+    Fuel,
+}
+
+pub enum ReloadScope {
+    Reference,
+    Brand,
+    Model,
+}
+
+pub enum AliasReview {
+    Approve,
+    MakeCanonical,
+    Reject,
+    Skip,
+    Stop,
+}
+
+pub enum OutputFormat {
+    Terminal,
+    Csv,
+    Json,
+}
+
+pub enum VehicleLookup {
+    ByCode,
+    ByBrand,
+}
+
 impl fmt::Display for MainMenu {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             MainMenu::Loads => write!(f, "📥 Loads"),
+            MainMenu::Reports => write!(f, "📊 Reports"),
             MainMenu::Maintenance => write!(f, "🛠️  Maintenance"),
             MainMenu::Exit => write!(f, "🔌 Exit"),
         }
@@ -36,6 +117,10 @@ impl fmt::Display for LoadMenu {
             LoadMenu::LoadBrands => write!(f, "Load Brands"),
             LoadMenu::LoadModels => write!(f, "Load Models"),
             LoadMenu::LoadYears => write!(f, "Load Years"),
+            LoadMenu::LoadYearsByReference => write!(f, "Load Years (per Reference)"),
+            LoadMenu::LoadYearsByBrand => write!(f, "Load Years (per Brand Year)"),
+            LoadMenu::LoadPrices => write!(f, "Load Prices"),
+            LoadMenu::Reload => write!(f, "Reload Reference / Brand / Model"),
             LoadMenu::Back => write!(f, "Back"),
         }
     }
@@ -46,7 +131,118 @@ impl fmt::Display for MaintMenu {
         match self {
             MaintMenu::RecreateDatabase => write!(f, "Recreate Database"),
             MaintMenu::CheckUpdates => write!(f, "Check for Updates"),
+            MaintMenu::CheckDatabase => write!(f, "Check Database Health"),
+            MaintMenu::BackfillAttributes => write!(f, "Backfill Model Attributes"),
+            MaintMenu::ReviewAliases => write!(f, "Review Name Aliases"),
+            MaintMenu::ImportInflation => write!(f, "Import Inflation Index"),
+            MaintMenu::Webhooks => write!(f, "Configure Webhooks"),
+            MaintMenu::DeleteReference => write!(f, "Delete Reference"),
+            MaintMenu::DeleteBrand => write!(f, "Delete Brand from Reference"),
             MaintMenu::Back => write!(f, "Back"),
         }
     }
 }
+
+impl fmt::Display for ReportMenu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReportMenu::PriceHistory => write!(f, "Price History"),
+            ReportMenu::ReferenceDiff => write!(f, "Reference Diff"),
+            ReportMenu::Depreciation => write!(f, "Depreciation Curves"),
+            ReportMenu::Search => write!(f, "Search Brands / Models"),
+            ReportMenu::Lifecycle => write!(f, "Launches & Discontinuations"),
+            ReportMenu::PriceLookup => write!(f, "Price Lookup by FIPE Code"),
+            ReportMenu::FleetValuation => write!(f, "Fleet Valuation"),
+            ReportMenu::PriceIndex => write!(f, "Price Index"),
+            ReportMenu::Anomalies => write!(f, "Price Anomalies"),
+            ReportMenu::Watchlist => write!(f, "Watchlist"),
+            ReportMenu::Back => write!(f, "Back"),
+        }
+    }
+}
+
+impl fmt::Display for WatchMenu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchMenu::Show => write!(f, "Show Watchlist"),
+            WatchMenu::CheckAlerts => write!(f, "Check Alerts Now"),
+            WatchMenu::Add => write!(f, "Add Vehicle"),
+            WatchMenu::Remove => write!(f, "Remove Vehicle"),
+            WatchMenu::Settings => write!(f, "Alert Settings"),
+            WatchMenu::Back => write!(f, "Back"),
+        }
+    }
+}
+
+impl fmt::Display for WebhookMenu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WebhookMenu::List => write!(f, "List Webhooks"),
+            WebhookMenu::Add => write!(f, "Add Webhook"),
+            WebhookMenu::Remove => write!(f, "Remove Webhook"),
+            WebhookMenu::Back => write!(f, "Back"),
+        }
+    }
+}
+
+impl fmt::Display for VehicleLookup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VehicleLookup::ByCode => write!(f, "By FIPE code"),
+            VehicleLookup::ByBrand => write!(f, "By brand / model / year"),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutputFormat::Terminal => write!(f, "Terminal"),
+            OutputFormat::Csv => write!(f, "CSV"),
+            OutputFormat::Json => write!(f, "JSON"),
+        }
+    }
+}
+
+impl fmt::Display for DepreciationScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DepreciationScope::Type => write!(f, "Per vehicle type"),
+            DepreciationScope::Brand => write!(f, "Per brand (within a type)"),
+            DepreciationScope::Model => write!(f, "Per model (within a brand)"),
+        }
+    }
+}
+
+impl fmt::Display for IndexScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexScope::Market => write!(f, "Whole market"),
+            IndexScope::Type => write!(f, "Per vehicle type"),
+            IndexScope::Brand => write!(f, "Per brand (within a type)"),
+            IndexScope::Fuel => write!(f, "Per fuel"),
+        }
+    }
+}
+
+impl fmt::Display for AliasReview {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AliasReview::Approve => write!(f, "Approve as alias"),
+            AliasReview::MakeCanonical => write!(f, "Approve and use as canonical name"),
+            AliasReview::Reject => write!(f, "Reject"),
+            AliasReview::Skip => write!(f, "Skip"),
+            AliasReview::Stop => write!(f, "Stop reviewing"),
+        }
+    }
+}
+
+impl fmt::Display for ReloadScope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReloadScope::Reference => write!(f, "Reference (brands)"),
+            ReloadScope::Brand => write!(f, "Brand (models)"),
+            ReloadScope::Model => write!(f, "Model (years)"),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use crate::menu::{OutputFormat, VehicleLookup};
+use crate::schema::Choice;
+use crate::selects::{select_choices, select_types, select_vehicle_by_code};
+use crate::sql::Sql;
+use inquire::ui::{Color, RenderConfig, Styled};
+use inquire::{CustomType, Select, Text};
+use rusqlite::Connection;
+
+pub fn render_config() -> RenderConfig<'static> {
+    RenderConfig::default()
+        .with_prompt_prefix(Styled::new(""))
+        .with_highlighted_option_prefix(Styled::new("> ").with_fg(Color::LightGreen))
+}
+
+// None when the list is empty, so callers don't have to special-case inquire's error.
+pub fn prompt_choice(
+    message: &str,
+    choices: Vec<Choice>,
+) -> Result<Option<Choice>, Box<dyn std::error::Error>> {
+    if choices.is_empty() {
+        return Ok(None);
+    }
+    let ans = Select::new(message, choices)
+        .with_render_config(render_config())
+        .prompt()?;
+    Ok(Some(ans))
+}
+
+pub fn prompt_fuel(conn: &Connection) -> Result<Option<Choice>, Box<dyn std::error::Error>> {
+    prompt_choice("Fuel", select_choices(conn, Sql::SelectFuelChoices, [])?)
+}
+
+pub fn prompt_type(conn: &Connection) -> Result<Option<Choice>, Box<dyn std::error::Error>> {
+    let types = select_types(conn)?
+        .into_iter()
+        .map(|t| Choice {
+            id: t.id,
+            description: t.description,
+        })
+        .collect();
+    prompt_choice("Type", types)
+}
+
+pub fn prompt_brand(
+    conn: &Connection,
+    type_id: i32,
+) -> Result<Option<Choice>, Box<dyn std::error::Error>> {
+    prompt_choice(
+        "Brand",
+        select_choices(conn, Sql::SelectBrandChoices, [type_id])?,
+    )
+}
+
+pub fn prompt_model(
+    conn: &Connection,
+    brand_id: i32,
+) -> Result<Option<Choice>, Box<dyn std::error::Error>> {
+    prompt_choice(
+        "Model",
+        select_choices(conn, Sql::SelectModelChoices, [brand_id])?,
+    )
+}
+
+// Returns the canonical vehicle id and a description of it.
+pub fn prompt_vehicle(
+    conn: &Connection,
+) -> Result<Option<(i32, String)>, Box<dyn std::error::Error>> {
+    let options = vec![VehicleLookup::ByCode, VehicleLookup::ByBrand];
+    let ans = Select::new("Find vehicle", options)
+        .with_render_config(render_config())
+        .prompt()?;
+
+    match ans {
+        VehicleLookup::ByCode => {
+            let code = Text::new("FIPE code (e.g. 005340-6):").prompt()?;
+            let year = CustomType::<i32>::new("Model year (32000 for zero km):").prompt()?;
+            let Some(fuel) = prompt_fuel(conn)? else {
+                return Ok(None);
+            };
+            let code = code.trim();
+            Ok(select_vehicle_by_code(conn, code, year, fuel.id)?
+                .map(|id| (id, format!("{} | {} | {}", code, year, fuel.description))))
+        }
+        VehicleLookup::ByBrand => {
+            let Some(tipo) = prompt_type(conn)? else {
+                return Ok(None);
+            };
+            let Some(brand) = prompt_brand(conn, tipo.id)? else {
+                return Ok(None);
+            };
+            let Some(model) = prompt_model(conn, brand.id)? else {
+                return Ok(None);
+            };
+            let vehicles = select_choices(conn, Sql::SelectVehicleChoices, [model.id])?;
+            let Some(vehicle) = prompt_choice("Year", vehicles)? else {
+                return Ok(None);
+            };
+            Ok(Some((
+                vehicle.id,
+                format!(
+                    "{} | {} | {}",
+                    brand.description, model.description, vehicle.description
+                ),
+            )))
+        }
+    }
+}
+
+pub fn prompt_reference(
+    conn: &Connection,
+    message: &str,
+    starting_cursor: usize,
+) -> Result<Option<Choice>, Box<dyn std::error::Error>> {
+    let references = select_choices(conn, Sql::SelectReferenceChoices, [])?;
+    if references.is_empty() {
+        return Ok(None);
+    }
+    let cursor = starting_cursor.min(references.len() - 1);
+    let ans = Select::new(message, references)
+        .with_render_config(render_config())
+        .with_starting_cursor(cursor)
+        .prompt()?;
+    Ok(Some(ans))
+}
+
+pub fn prompt_output() -> Result<OutputFormat, Box<dyn std::error::Error>> {
+    let options = vec![
+        OutputFormat::Terminal,
+        OutputFormat::Csv,
+        OutputFormat::Json,
+    ];
+    let ans = Select::new("Output", options)
+        .with_render_config(render_config())
+        .prompt()?;
+    Ok(ans)
+}
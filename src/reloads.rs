@@ -0,0 +1,390 @@
+use crate::aliases::track_label;
+use crate::attributes::parse_model_attributes;
+use crate::label::Label;
+use crate::lifecycle::refresh_lifecycle;
+use crate::loads::{fetch_fipe, log_failed_request};
+use crate::menu::ReloadScope;
+use crate::prompts::{prompt_choice, prompt_reference, prompt_type, render_config};
+use crate::schema::{FipeStruct, ModelsResponse, ReloadChange, ReloadRow};
+use crate::selects::{select_choices, select_types};
+use crate::sql::Sql;
+use crate::utils::{parse_year, print_table, progress_bar, throttle};
+use indicatif::ProgressBar;
+use inquire::{Confirm, Select};
+use rusqlite::{params, Connection, Params};
+
+const BRANDS_URL: &str = "https://veiculos.fipe.org.br/api/veiculos/ConsultarMarcas";
+const MODELS_URL: &str = "https://veiculos.fipe.org.br/api/veiculos/ConsultarModelos";
+const YEARS_URL: &str = "https://veiculos.fipe.org.br/api/veiculos/ConsultarAnoModelo";
+
+const RELOAD_HEADERS: [&str; 4] = ["Kind", "Change", "Reference", "Label"];
+
+pub fn select_reload_rows<P: Params>(
+    conn: &Connection,
+    sql: Sql,
+    params: P,
+) -> Result<Vec<ReloadRow>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(sql.get().as_str())?;
+    let rows = stmt.query_map(params, |row| {
+        Ok(ReloadRow {
+            id: row.get("id")?,
+            fipe: row.get("fipe")?,
+            description: row.get("description")?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<ReloadRow>, rusqlite::Error>>()?)
+}
+
+pub fn log_change(
+    conn: &Connection,
+    kind: &str,
+    change: &str,
+    entity_id: i32,
+    label: &str,
+    ref_id: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    conn.prepare_cached(Sql::InsertReloadLog.get().as_str())?
+        .execute(params![kind, change, entity_id, label, ref_id])?;
+    Ok(())
+}
+
+// Rows the API no longer returns are flagged in the log; they stay loaded until deleted.
+fn flag_removed(
+    conn: &Connection,
+    kind: &str,
+    existing: &[ReloadRow],
+    fetched: &[String],
+    ref_id: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for row in existing {
+        if !fetched.contains(&row.fipe) {
+            log_change(conn, kind, "removed", row.id, &row.description, ref_id)?;
+        }
+    }
+    Ok(())
+}
+
+async fn reload_years(
+    conn: &Connection,
+    pb: &ProgressBar,
+    model_ref_id: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (ref_id, type_id, ref_fipe, brand_fipe, model_fipe): (i32, i32, String, String, String) =
+        conn.query_row(
+            Sql::SelectModelRefKeys.get().as_str(),
+            [model_ref_id],
+            |row| {
+                Ok((
+                    row.get("ref_id")?,
+                    row.get("type_id")?,
+                    row.get("ref_fipe")?,
+                    row.get("brand_fipe")?,
+                    row.get("model_fipe")?,
+                ))
+            },
+        )?;
+    let body = serde_json::json!({
+        "codigoTipoVeiculo": type_id,
+        "codigoTabelaReferencia": ref_fipe,
+        "codigoMarca": brand_fipe,
+        "codigoModelo": model_fipe
+    });
+    let response = fetch_fipe(YEARS_URL, &body).await.unwrap();
+    let years: Vec<FipeStruct> = response.json().await?;
+
+    let existing = select_reload_rows(conn, Sql::SelectReloadYears, [model_ref_id])?;
+    for y in &years {
+        let Some(year) = parse_year(&y.value) else {
+            log_failed_request(conn, YEARS_URL, &body, "invalid year code", Some(&y.value))?;
+            continue;
+        };
+        let inserted = conn
+            .prepare_cached(Sql::InsertVehicle.get().as_str())?
+            .execute(params![
+                y.label,
+                year.value,
+                y.value,
+                model_ref_id,
+                year.fuel_id,
+                year.model_year,
+                year.is_zero_km
+            ])?;
+        let (vehicle_id, description, value): (i32, String, Option<String>) = conn.query_row(
+            Sql::SelectVehicleDescriptor.get().as_str(),
+            params![y.value, model_ref_id],
+            |row| Ok((row.get("id")?, row.get("description")?, row.get("value")?)),
+        )?;
+        if inserted == 0 && (description != y.label || value != year.value) {
+            conn.execute(
+                Sql::UpdateVehicleDescriptor.get().as_str(),
+                params![
+                    y.label,
+                    year.value,
+                    year.fuel_id,
+                    year.model_year,
+                    year.is_zero_km,
+                    vehicle_id
+                ],
+            )?;
+            log_change(conn, "year", "updated", vehicle_id, &y.label, ref_id)?;
+        }
+        if !existing.iter().any(|e| e.fipe == y.value) {
+            conn.execute(
+                Sql::InsertYear.get().as_str(),
+                params![y.value, model_ref_id],
+            )?;
+            log_change(conn, "year", "added", vehicle_id, &y.label, ref_id)?;
+        }
+    }
+    let fetched: Vec<String> = years.iter().map(|y| y.value.clone()).collect();
+    flag_removed(conn, "year", &existing, &fetched, ref_id)?;
+    pb.inc(1);
+    throttle().await;
+    Ok(())
+}
+
+async fn reload_models(
+    conn: &Connection,
+    pb: &ProgressBar,
+    brand_ref_id: i32,
+    deep: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (ref_id, type_id, ref_fipe, brand_fipe): (i32, i32, String, String) = conn.query_row(
+        Sql::SelectBrandRefKeys.get().as_str(),
+        [brand_ref_id],
+        |row| {
+            Ok((
+                row.get("ref_id")?,
+                row.get("type_id")?,
+                row.get("ref_fipe")?,
+                row.get("brand_fipe")?,
+            ))
+        },
+    )?;
+    let body = serde_json::json!({
+        "codigoTipoVeiculo": type_id,
+        "codigoTabelaReferencia": ref_fipe,
+        "codigoMarca": brand_fipe
+    });
+    let response = fetch_fipe(MODELS_URL, &body).await.unwrap();
+    let models: ModelsResponse = response.json().await?;
+
+    for y in &models.years {
+        conn.prepare_cached(Sql::InsertBrandYear.get().as_str())?
+            .execute(params![brand_ref_id, y.value, y.label])?;
+    }
+    let existing = select_reload_rows(conn, Sql::SelectReloadModels, [brand_ref_id])?;
+    for m in &models.model {
+        let a = parse_model_attributes(&m.label);
+        let inserted = conn
+            .prepare_cached(Sql::InsertModelDescriptor.get().as_str())?
+            .execute(params![
+                m.label,
+                m.value,
+                brand_ref_id,
+                a.engine_displacement,
+                a.valves,
+                a.doors,
+                a.transmission,
+                a.turbo,
+                a.body_style,
+                a.fuel
+            ])?;
+        let (descriptor_id, description): (i32, String) = conn.query_row(
+            Sql::SelectModelDescriptor.get().as_str(),
+            params![m.value, brand_ref_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        // The descriptor keeps its name; the new label waits in alias review.
+        if inserted == 0 && description != m.label {
+            track_label(conn, "model", descriptor_id, &description, &m.label, ref_id)?;
+            log_change(conn, "model", "alias", descriptor_id, &m.label, ref_id)?;
+        }
+        let fipe = m.value.to_string();
+        if !existing.iter().any(|e| e.fipe == fipe) {
+            conn.execute(
+                Sql::InsertModel.get().as_str(),
+                params![m.value, brand_ref_id],
+            )?;
+            log_change(conn, "model", "added", descriptor_id, &m.label, ref_id)?;
+        }
+    }
+    let fetched: Vec<String> = models.model.iter().map(|m| m.value.to_string()).collect();
+    flag_removed(conn, "model", &existing, &fetched, ref_id)?;
+    pb.inc(1);
+    throttle().await;
+
+    if deep {
+        let model_refs = select_reload_rows(conn, Sql::SelectReloadModels, [brand_ref_id])?;
+        pb.inc_length(model_refs.len() as u64);
+        for mr in &model_refs {
+            reload_years(conn, pb, mr.id).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn reload_brands(
+    conn: &Connection,
+    pb: &ProgressBar,
+    ref_id: i32,
+    ref_fipe: &str,
+    type_id: i32,
+    deep: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = serde_json::json!({
+        "codigoTipoVeiculo": type_id,
+        "codigoTabelaReferencia": ref_fipe
+    });
+    let response = fetch_fipe(BRANDS_URL, &body).await.unwrap();
+    let brands: Vec<FipeStruct> = response.json().await?;
+
+    let existing = select_reload_rows(conn, Sql::SelectReloadBrands, params![ref_id, type_id])?;
+    for b in &brands {
+        let inserted = conn
+            .prepare_cached(Sql::InsertBrandDescriptor.get().as_str())?
+            .execute(params![b.label, b.value, type_id])?;
+        let (descriptor_id, description): (i32, String) = conn.query_row(
+            Sql::SelectBrandDescriptor.get().as_str(),
+            params![b.value, type_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        // The descriptor keeps its name; the new label waits in alias review.
+        if inserted == 0 && description != b.label {
+            track_label(conn, "brand", descriptor_id, &description, &b.label, ref_id)?;
+            log_change(conn, "brand", "alias", descriptor_id, &b.label, ref_id)?;
+        }
+        if !existing.iter().any(|e| e.fipe == b.value) {
+            conn.execute(
+                Sql::InsertBrand.get().as_str(),
+                params![b.value, type_id, ref_id],
+            )?;
+            log_change(conn, "brand", "added", descriptor_id, &b.label, ref_id)?;
+        }
+    }
+    let fetched: Vec<String> = brands.iter().map(|b| b.value.clone()).collect();
+    flag_removed(conn, "brand", &existing, &fetched, ref_id)?;
+    pb.inc(1);
+    throttle().await;
+
+    if deep {
+        let brand_refs =
+            select_reload_rows(conn, Sql::SelectReloadBrands, params![ref_id, type_id])?;
+        pb.inc_length(brand_refs.len() as u64);
+        for br in &brand_refs {
+            reload_models(conn, pb, br.id, deep).await?;
+        }
+    }
+    Ok(())
+}
+
+fn select_reload_changes(
+    conn: &Connection,
+    since: i64,
+) -> Result<Vec<ReloadChange>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectReloadLogSince.get().as_str())?;
+    let rows = stmt.query_map([since], |row| {
+        Ok(ReloadChange {
+            kind: row.get("kind")?,
+            change: row.get("change")?,
+            ref_date: row.get("ref_date")?,
+            label: row.get("label")?,
+        })
+    })?;
+    Ok(rows.collect::<Result<Vec<ReloadChange>, rusqlite::Error>>()?)
+}
+
+// Re-fetches one reference's brands, one brand's models or one model's years and applies
+// the differences, instead of skipping parents that already have children like the loads do.
+pub async fn reload(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let options = vec![
+        ReloadScope::Reference,
+        ReloadScope::Brand,
+        ReloadScope::Model,
+    ];
+    let scope = Select::new("Reload", options)
+        .with_render_config(render_config())
+        .prompt()?;
+    let Some(reference) = prompt_reference(conn, "Reference", 0)? else {
+        Label::NoReferences.log();
+        return Ok(());
+    };
+
+    let checkpoint: i64 =
+        conn.query_row(Sql::SelectReloadLogCheckpoint.get().as_str(), [], |row| {
+            row.get(0)
+        })?;
+    match scope {
+        ReloadScope::Reference => {
+            let deep = Confirm::new("Also reload every brand's models and years?")
+                .with_default(false)
+                .prompt()?;
+            let ref_fipe: String = conn.query_row(
+                Sql::SelectReferenceFipe.get().as_str(),
+                [reference.id],
+                |row| row.get(0),
+            )?;
+            let types = select_types(conn)?;
+            let pb = progress_bar(types.len() as u64);
+            for t in &types {
+                reload_brands(conn, &pb, reference.id, &ref_fipe, t.id, deep).await?;
+            }
+            pb.finish_and_clear();
+        }
+        ReloadScope::Brand | ReloadScope::Model => {
+            let Some(tipo) = prompt_type(conn)? else {
+                return Ok(());
+            };
+            let brands = select_choices(
+                conn,
+                Sql::SelectReferenceBrandChoices,
+                [reference.id, tipo.id],
+            )?;
+            let Some(brand) = prompt_choice("Brand", brands)? else {
+                Label::NothingLoaded.log();
+                return Ok(());
+            };
+            if let ReloadScope::Brand = scope {
+                let deep = Confirm::new("Also reload every model's years?")
+                    .with_default(false)
+                    .prompt()?;
+                let pb = progress_bar(1);
+                reload_models(conn, &pb, brand.id, deep).await?;
+                pb.finish_and_clear();
+            } else {
+                let models = select_choices(conn, Sql::SelectReferenceModelChoices, [brand.id])?;
+                let Some(model) = prompt_choice("Model", models)? else {
+                    Label::NothingLoaded.log();
+                    return Ok(());
+                };
+                let pb = progress_bar(1);
+                reload_years(conn, &pb, model.id).await?;
+                pb.finish_and_clear();
+            }
+        }
+    }
+    refresh_lifecycle(conn)?;
+
+    let changes = select_reload_changes(conn, checkpoint)?;
+    if changes.is_empty() {
+        Label::NoReloadChanges.log();
+        return Ok(());
+    }
+    (Label::ReloadTitle {
+        count: &changes.len().to_string(),
+    })
+    .log();
+    let rows: Vec<Vec<String>> = changes
+        .iter()
+        .map(|c| {
+            vec![
+                c.kind.clone(),
+                c.change.clone(),
+                c.ref_date.clone().unwrap_or_default(),
+                c.label.clone(),
+            ]
+        })
+        .collect();
+    print_table(&RELOAD_HEADERS, &rows);
+    Ok(())
+}
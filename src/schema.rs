@@ -1,4 +1,4 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // Types
 #[derive(Debug, Deserialize)]
@@ -38,6 +38,7 @@ pub struct Brands {
 // Models
 pub struct Models {
     pub id: i32,
+    pub model_id: i32,
     pub description: String,
     pub fipe: String,
     pub ref_id: String,
@@ -48,6 +49,17 @@ pub struct Models {
     pub brand_description: String,
 }
 
+#[derive(Debug, Default)]
+pub struct ModelAttributes {
+    pub engine_displacement: Option<f64>,
+    pub valves: Option<i32>,
+    pub doors: Option<i32>,
+    pub transmission: Option<String>,
+    pub turbo: bool,
+    pub body_style: Option<String>,
+    pub fuel: Option<String>,
+}
+
 pub struct ModelsReplicate {
     pub id: i32,
     pub description: String,
@@ -58,6 +70,8 @@ pub struct ModelsReplicate {
 pub struct ModelsResponse {
     #[serde(rename = "Modelos")]
     pub model: Vec<FipeModels>,
+    #[serde(rename = "Anos", default)]
+    pub years: Vec<FipeStruct>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -68,6 +82,313 @@ pub struct FipeModels {
     pub value: i32,
 }
 
+// Brand years
+pub struct BrandYears {
+    pub id: i32,
+    pub brand_ref_id: i32,
+    pub fipe: String,
+    pub description: String,
+    pub ref_id: String,
+    pub ref_date: String,
+    pub type_id: i32,
+    pub type_description: String,
+    pub brand_id: String,
+    pub brand_description: String,
+}
+
+// Years
+pub struct ParsedYear {
+    pub value: Option<String>,
+    pub model_year: i32,
+    pub is_zero_km: bool,
+    pub fuel_id: Option<String>,
+}
+
+// Prices
+pub struct Prices {
+    pub id: i32,
+    pub ref_id: String,
+    pub ref_date: String,
+    pub type_id: i32,
+    pub type_description: String,
+    pub brand_id: String,
+    pub brand_description: String,
+    pub model_id: String,
+    pub model_description: String,
+    pub description: String,
+    pub model_year: i32,
+    pub fuel_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceResponse {
+    #[serde(rename = "Valor")]
+    pub valor: String,
+    #[serde(rename = "CodigoFipe")]
+    pub codigo_fipe: String,
+    #[serde(rename = "Marca", default)]
+    pub marca: String,
+    #[serde(rename = "Modelo", default)]
+    pub modelo: String,
+}
+
+// Price by FIPE code
+pub struct PriceLookup {
+    pub brand: String,
+    pub model: String,
+    pub price: f64,
+    pub source: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PriceHistory {
+    pub ref_date: String,
+    pub fipe: String,
+    pub price: f64,
+    pub month_change: Option<f64>,
+    pub year_change: Option<f64>,
+    pub real_price: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VehicleHistory {
+    pub vehicle: String,
+    pub entries: Vec<PriceHistory>,
+    pub inflation: Option<String>,
+}
+
+// Diff
+#[derive(Debug, Serialize)]
+pub struct DiffEntry {
+    pub category: String,
+    pub change: String,
+    pub type_description: String,
+    pub brand: String,
+    pub model: Option<String>,
+    pub year: Option<String>,
+    pub old_price: Option<f64>,
+    pub new_price: Option<f64>,
+    pub pct_change: Option<f64>,
+    pub real_old_price: Option<f64>,
+    pub real_new_price: Option<f64>,
+    pub real_pct_change: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferenceDiff {
+    pub from: String,
+    pub to: String,
+    #[serde(skip)]
+    pub from_date: String,
+    #[serde(skip)]
+    pub to_date: String,
+    pub entries: Vec<DiffEntry>,
+    pub inflation: Option<String>,
+}
+
+// Fleet valuation
+pub struct FleetVehicle {
+    pub asset_id: String,
+    pub fipe_code: Option<String>,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub model_year: Option<i32>,
+    pub fuel: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FleetEntry {
+    pub asset_id: String,
+    pub input: String,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<String>,
+    pub confidence: String,
+    pub price: Option<f64>,
+    pub note: Option<String>,
+    #[serde(skip)]
+    pub vehicle_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FleetTotal {
+    pub reference: String,
+    pub vehicles: usize,
+    pub total: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FleetValuation {
+    pub reference: Option<String>,
+    pub entries: Vec<FleetEntry>,
+    pub totals: Vec<FleetTotal>,
+}
+
+// Depreciation
+pub struct DepreciationPrice {
+    pub ref_id: i32,
+    pub ref_year: i32,
+    pub type_description: String,
+    pub brand_description: String,
+    pub model_id: i32,
+    pub model_description: String,
+    pub fuel_id: Option<i32>,
+    pub model_year: i32,
+    pub is_zero_km: bool,
+    pub price: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DepreciationPoint {
+    pub group: String,
+    pub age: i32,
+    pub avg_price: f64,
+    pub retention: f64,
+    pub yearly_change: Option<f64>,
+    pub samples: usize,
+}
+
+// Price index
+pub struct IndexRelative {
+    pub ref_month: String,
+    pub type_description: String,
+    pub brand_description: String,
+    pub fuel_description: String,
+    pub relative: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IndexPoint {
+    pub group: String,
+    pub reference: String,
+    pub index: f64,
+    pub monthly_change: Option<f64>,
+    pub samples: usize,
+}
+
+// Anomalies
+pub struct PriceRelative {
+    pub id: i32,
+    pub model_id: i32,
+    pub ref_month: String,
+    pub old_price: f64,
+    pub new_price: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Anomaly {
+    pub reference: String,
+    pub kind: String,
+    pub type_description: String,
+    pub brand: String,
+    pub model: String,
+    pub year: String,
+    pub fipe_code: Option<String>,
+    pub detail: String,
+    pub ratio: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnomalySummary {
+    pub reference: String,
+    pub kind: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnomalyReport {
+    pub summary: Vec<AnomalySummary>,
+    pub anomalies: Vec<Anomaly>,
+}
+
+// Watchlist
+#[derive(Debug, Serialize)]
+pub struct WatchItem {
+    pub id: i32,
+    pub fipe_code: String,
+    pub model_year: i32,
+    pub fuel: String,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    #[serde(skip)]
+    pub alerted_ref_id: Option<i32>,
+    #[serde(skip)]
+    pub ref_id: Option<i32>,
+    pub reference: Option<String>,
+    pub new_price: Option<f64>,
+    pub old_reference: Option<String>,
+    pub old_price: Option<f64>,
+    pub pct_change: Option<f64>,
+}
+
+pub struct WatchSettings {
+    pub threshold: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatchAlerts<'a> {
+    pub threshold: f64,
+    pub alerts: Vec<&'a WatchItem>,
+}
+
+// Webhooks
+pub struct Webhook {
+    pub id: i32,
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: String,
+}
+
+pub struct WebhookEvent {
+    pub id: i32,
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: String,
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+// Search
+pub struct SearchResult {
+    pub kind: String,
+    pub entity_id: i32,
+    pub brand: String,
+    pub model: String,
+    pub type_description: Option<String>,
+}
+
+pub struct ReferenceSpan {
+    pub first_ref: Option<String>,
+    pub last_ref: Option<String>,
+    pub ref_count: i32,
+}
+
+// Lifecycle
+#[derive(Debug, Serialize)]
+pub struct LifecycleEvent {
+    pub event: String,
+    pub kind: String,
+    pub ref_date: String,
+    pub type_description: String,
+    pub brand: String,
+    pub model: String,
+    pub year: Option<String>,
+}
+
+// Aliases
+pub struct Alias {
+    pub id: i32,
+    pub kind: String,
+    pub entity_id: i32,
+    pub canonical_id: i32,
+    pub canonical: String,
+    pub label: String,
+    pub match_kind: String,
+    pub score: f64,
+    pub status: String,
+    pub ref_date: Option<String>,
+}
+
 // Generic
 #[derive(Debug, Deserialize)]
 pub struct FipeStruct {
@@ -77,19 +398,64 @@ pub struct FipeStruct {
     pub value: String,
 }
 
+// Reloads
+pub struct ReloadRow {
+    pub id: i32,
+    pub fipe: String,
+    pub description: String,
+}
+
+pub struct ReloadChange {
+    pub kind: String,
+    pub change: String,
+    pub ref_date: Option<String>,
+    pub label: String,
+}
+
+// Health check
+pub struct HealthRow {
+    pub ref_date: String,
+    pub type_description: String,
+    pub brands: i32,
+    pub brands_with_models: i32,
+    pub models: i32,
+    pub models_with_years: i32,
+}
+
+pub struct HealthIssue {
+    pub issue: String,
+    pub ref_date: Option<String>,
+    pub type_description: Option<String>,
+    pub brand: Option<String>,
+    pub model: Option<String>,
+    pub year: Option<String>,
+}
+
 // Utilities
 #[derive(Debug, Deserialize)]
 pub struct Status {
     pub db_status: String,
     pub last_update: Option<String>,
+    pub latest_remote_reference: Option<String>,
 }
 
+// Average rows one parent yields, used to size load progress bars.
 #[derive(Debug, Deserialize)]
-pub struct RowCount {
-    pub brands_rowcount: i32,
-    pub models_rowcount: i32,
-    pub years_rowcount: i32,
-    pub vehicles_rowcount: i32,
+pub struct LoadEstimates {
+    pub brands_per_parent: f64,
+    pub models_per_parent: f64,
+    pub years_per_parent: f64,
+}
+
+pub struct Choice {
+    pub id: i32,
+    pub description: String,
+}
+
+impl std::fmt::Display for Choice {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
 }
 
 pub struct Count {
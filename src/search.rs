@@ -0,0 +1,143 @@
+use crate::label::Label;
+use crate::schema::{ReferenceSpan, SearchResult};
+use crate::sql::Sql;
+use crate::utils::{print_table, ZERO_KM_YEAR};
+use inquire::Text;
+use rusqlite::{params, Connection};
+
+const SEARCH_LIMIT: i32 = 50;
+
+// Every word is matched as a prefix, so "gol 1.0 fle" finds "Gol 1.0 Mi Total Flex 8V 4p".
+pub fn fts_query(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn search(
+    conn: &Connection,
+    input: &str,
+    limit: i32,
+) -> Result<Vec<SearchResult>, Box<dyn std::error::Error>> {
+    let query = fts_query(input);
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut stmt = conn.prepare(Sql::SelectSearch.get().as_str())?;
+    let result_iter = stmt.query_map(params![query, limit], |row| {
+        Ok(SearchResult {
+            kind: row.get("kind")?,
+            entity_id: row.get("entity_id")?,
+            brand: row.get("brand")?,
+            model: row.get("model")?,
+            type_description: row.get("type_description")?,
+        })
+    })?;
+
+    let mut results = Vec::new();
+    for result in result_iter {
+        results.push(result?);
+    }
+    Ok(results)
+}
+
+pub fn select_reference_span(
+    conn: &Connection,
+    result: &SearchResult,
+) -> Result<ReferenceSpan, Box<dyn std::error::Error>> {
+    let sql = if result.kind == "brand" {
+        Sql::SelectSearchBrandRefs
+    } else {
+        Sql::SelectSearchModelRefs
+    };
+    let span = conn.query_row(sql.get().as_str(), [result.entity_id], |row| {
+        Ok(ReferenceSpan {
+            first_ref: row.get("first_ref")?,
+            last_ref: row.get("last_ref")?,
+            ref_count: row.get("ref_count")?,
+        })
+    })?;
+    Ok(span)
+}
+
+// "2008-2011, 2014, 0 km"
+pub fn select_model_years(
+    conn: &Connection,
+    model_id: i32,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectSearchModelYears.get().as_str())?;
+    let year_iter = stmt.query_map([model_id], |row| {
+        let model_year: i32 = row.get("model_year")?;
+        let is_zero_km: bool = row.get("is_zero_km")?;
+        Ok((model_year, is_zero_km))
+    })?;
+
+    let mut ranges: Vec<(i32, i32)> = Vec::new();
+    let mut zero_km = false;
+    for year in year_iter {
+        let (model_year, is_zero_km) = year?;
+        if is_zero_km || model_year == ZERO_KM_YEAR {
+            zero_km = true;
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.1 + 1 == model_year => last.1 = model_year,
+            _ => ranges.push((model_year, model_year)),
+        }
+    }
+
+    let mut parts: Vec<String> = ranges
+        .iter()
+        .map(|(from, to)| {
+            if from == to {
+                from.to_string()
+            } else {
+                format!("{}-{}", from, to)
+            }
+        })
+        .collect();
+    if zero_km {
+        parts.push("0 km".to_string());
+    }
+    Ok(parts.join(", "))
+}
+
+pub fn show_search(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let input = Text::new("Search brands and models:").prompt()?;
+    let results = search(conn, &input, SEARCH_LIMIT)?;
+
+    (Label::SearchTitle {
+        query: input.trim(),
+        count: &results.len().to_string(),
+    })
+    .log();
+
+    let mut rows = Vec::new();
+    for result in &results {
+        let span = select_reference_span(conn, result)?;
+        let references = match (span.first_ref, span.last_ref) {
+            (Some(first), Some(last)) => format!("{} to {} ({})", first, last, span.ref_count),
+            _ => "-".to_string(),
+        };
+        let years = if result.kind == "model" {
+            select_model_years(conn, result.entity_id)?
+        } else {
+            String::new()
+        };
+        rows.push(vec![
+            result.kind.clone(),
+            result.type_description.clone().unwrap_or_default(),
+            result.brand.clone(),
+            result.model.clone(),
+            references,
+            years,
+        ]);
+    }
+    print_table(
+        &["Kind", "Type", "Brand", "Model", "References", "Years"],
+        &rows,
+    );
+    Ok(())
+}
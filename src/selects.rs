@@ -1,7 +1,9 @@
 use crate::label::Label;
-use crate::schema::{Brands, Models, ModelsReplicate, References, Types};
+use crate::schema::{
+    BrandYears, Brands, Choice, Models, ModelsReplicate, Prices, References, Types,
+};
 use crate::sql::Sql;
-use rusqlite::{Connection, Result};
+use rusqlite::{params, Connection, Params, Result};
 pub fn select_types(conn: &Connection) -> Result<Vec<Types>, Box<dyn std::error::Error>> {
     let mut stmt = match conn.prepare(Sql::SelectTypes.get().as_str()) {
         Ok(s) => s,
@@ -138,6 +140,7 @@ pub fn select_models(conn: &Connection) -> Result<Vec<Models>, Box<dyn std::erro
     let model_iter = stmt.query_map([], |row| {
         Ok(Models {
             id: row.get("id")?,
+            model_id: row.get("model_id")?,
             description: row.get("description")?,
             fipe: row.get("fipe")?,
             ref_id: row.get("ref_id")?,
@@ -158,7 +161,7 @@ pub fn select_models(conn: &Connection) -> Result<Vec<Models>, Box<dyn std::erro
 
 pub fn select_models_replicate(
     conn: &Connection,
-    fipe: &str,
+    model_id: i32,
 ) -> Result<Vec<ModelsReplicate>, Box<dyn std::error::Error>> {
     let mut stmt = match conn.prepare(Sql::SelectModelsReplicate.get().as_str()) {
         Ok(s) => s,
@@ -172,7 +175,7 @@ pub fn select_models_replicate(
         }
     };
 
-    let model_iter = stmt.query_map([fipe], |row| {
+    let model_iter = stmt.query_map([model_id], |row| {
         Ok(ModelsReplicate {
             id: row.get("id")?,
             description: row.get("description")?,
@@ -186,3 +189,167 @@ pub fn select_models_replicate(
     }
     Ok(models)
 }
+
+pub fn select_models_by_reference(
+    conn: &Connection,
+) -> Result<Vec<Models>, Box<dyn std::error::Error>> {
+    let mut stmt = match conn.prepare(Sql::SelectModelsByReference.get().as_str()) {
+        Ok(s) => s,
+
+        Err(rusqlite::Error::SqliteFailure(e, Some(msg))) if msg.contains("no such table") => {
+            Label::TableNotExist.log();
+            return Err(Box::new(e));
+        }
+        Err(e) => {
+            return Err(Box::new(e));
+        }
+    };
+
+    let model_iter = stmt.query_map([], |row| {
+        Ok(Models {
+            id: row.get("id")?,
+            model_id: row.get("model_id")?,
+            description: row.get("description")?,
+            fipe: row.get("fipe")?,
+            ref_id: row.get("ref_id")?,
+            ref_date: row.get("ref_date")?,
+            type_id: row.get("type_id")?,
+            type_description: row.get("type_description")?,
+            brand_id: row.get("brand_id")?,
+            brand_description: row.get("brand_description")?,
+        })
+    })?;
+
+    let mut models = Vec::new();
+    for model in model_iter {
+        models.push(model?);
+    }
+    Ok(models)
+}
+
+pub fn select_brand_years(conn: &Connection) -> Result<Vec<BrandYears>, Box<dyn std::error::Error>> {
+    let mut stmt = match conn.prepare(Sql::SelectBrandYears.get().as_str()) {
+        Ok(s) => s,
+
+        Err(rusqlite::Error::SqliteFailure(e, Some(msg))) if msg.contains("no such table") => {
+            Label::TableNotExist.log();
+            return Err(Box::new(e));
+        }
+        Err(e) => {
+            return Err(Box::new(e));
+        }
+    };
+
+    let year_iter = stmt.query_map([], |row| {
+        Ok(BrandYears {
+            id: row.get("id")?,
+            brand_ref_id: row.get("brand_ref_id")?,
+            fipe: row.get("fipe")?,
+            description: row.get("description")?,
+            ref_id: row.get("ref_id")?,
+            ref_date: row.get("ref_date")?,
+            type_id: row.get("type_id")?,
+            type_description: row.get("type_description")?,
+            brand_id: row.get("brand_id")?,
+            brand_description: row.get("brand_description")?,
+        })
+    })?;
+
+    let mut years = Vec::new();
+    for year in year_iter {
+        years.push(year?);
+    }
+    Ok(years)
+}
+
+// Prices
+
+pub fn select_prices(conn: &Connection) -> Result<Vec<Prices>, Box<dyn std::error::Error>> {
+    let mut stmt = match conn.prepare(Sql::SelectPrices.get().as_str()) {
+        Ok(s) => s,
+
+        Err(rusqlite::Error::SqliteFailure(e, Some(msg))) if msg.contains("no such table") => {
+            Label::TableNotExist.log();
+            return Err(Box::new(e));
+        }
+        Err(e) => {
+            return Err(Box::new(e));
+        }
+    };
+
+    let price_iter = stmt.query_map([], |row| {
+        Ok(Prices {
+            id: row.get("id")?,
+            ref_id: row.get("ref_id")?,
+            ref_date: row.get("ref_date")?,
+            type_id: row.get("type_id")?,
+            type_description: row.get("type_description")?,
+            brand_id: row.get("brand_id")?,
+            brand_description: row.get("brand_description")?,
+            model_id: row.get("model_id")?,
+            model_description: row.get("model_description")?,
+            description: row.get("description")?,
+            model_year: row.get("model_year")?,
+            fuel_id: row.get("fuel_id")?,
+        })
+    })?;
+
+    let mut prices = Vec::new();
+    for price in price_iter {
+        prices.push(price?);
+    }
+    Ok(prices)
+}
+
+// Choices (brand -> model -> vehicle pickers)
+
+pub fn select_choices<P: Params>(
+    conn: &Connection,
+    sql: Sql,
+    params: P,
+) -> Result<Vec<Choice>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(sql.get().as_str())?;
+    let choice_iter = stmt.query_map(params, |row| {
+        Ok(Choice {
+            id: row.get("id")?,
+            description: row.get("description")?,
+        })
+    })?;
+
+    let mut choices = Vec::new();
+    for choice in choice_iter {
+        choices.push(choice?);
+    }
+    Ok(choices)
+}
+
+// FIPE sometimes lists one code under two model descriptors, which makes two vehicle rows
+// for it; such a match is refused rather than resolved to whichever row comes first.
+pub fn ambiguous_code(
+    fipe_code: &str,
+    model_year: i32,
+    matches: usize,
+) -> Box<dyn std::error::Error> {
+    format!(
+        "FIPE code {} ({}) matches {} loaded vehicles",
+        fipe_code, model_year, matches
+    )
+    .into()
+}
+
+pub fn select_vehicle_by_code(
+    conn: &Connection,
+    fipe_code: &str,
+    model_year: i32,
+    fuel_id: i32,
+) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectVehicleByCode.get().as_str())?;
+    let ids = stmt
+        .query_map(params![fipe_code, model_year, fuel_id], |row| row.get(0))?
+        .collect::<Result<Vec<i32>, rusqlite::Error>>()?;
+    match ids.as_slice() {
+        [] => Ok(None),
+        [id] => Ok(Some(*id)),
+        _ => Err(ambiguous_code(fipe_code, model_year, ids.len())),
+    }
+}
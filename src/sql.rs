@@ -1,15 +1,32 @@
+// Bump together with a new `Sql::Migrate` arm whenever the schema changes.
+pub const SCHEMA_VERSION: i32 = 21;
+
 pub enum Sql {
     // setup
     DropTables,
-    CreateYears,
+    CreateVehicleReferences,
+    CreateVehicles,
     CreateModels,
     CreateBrands,
+    CreateViews,
+    CreateSearchIndex,
+    CreateAliases,
+    CreateLifecycle,
+    CreateReloadLog,
+    CreateBrandYears,
+    CreatePriceLookups,
+    CreateInflationIndex,
+    CreateFailedRequests,
+    CreateAnomalies,
+    CreateWatchlist,
+    CreateWebhooks,
     CreateReferences,
     CreateTypes,
     CreateFuels,
     InitFuels,
     InitTypes,
     CreateIndexes,
+    EnableForeignKeys,
     CreateConfig,
 
     // selects
@@ -19,17 +36,123 @@ pub enum Sql {
     SelectBrands,
     SelectModels,
     SelectModelsReplicate,
+    SelectModelsByReference,
+    SelectBrandYears,
+    SelectModelRefByFipe,
+    SelectPrices,
+    SelectPricesCount,
+    SelectFuelChoices,
+    SelectBrandChoices,
+    SelectModelChoices,
+    SelectVehicleChoices,
+    SelectVehicleByCode,
+    SelectLatestReference,
+    SelectReferenceDate,
+    SelectLocalPriceByCode,
+    SelectCodeTypes,
+    SelectCachedPrice,
+    SelectFleetVehicleByCode,
+    SelectFleetVehicleByModel,
+    SelectPriceHistory,
+    SelectReferenceChoices,
+    SelectReferenceBrandChoices,
+    SelectReferenceModelChoices,
+    SelectReferenceFipe,
+    SelectBrandRefKeys,
+    SelectModelRefKeys,
+    SelectReloadBrands,
+    SelectReloadModels,
+    SelectReloadYears,
+    SelectVehicleDescriptor,
+    SelectReloadLogCheckpoint,
+    SelectReloadLogSince,
+    SelectDiffBrands,
+    SelectDiffModels,
+    SelectDiffVehicles,
+    SelectDiffPrices,
+    SelectDepreciationPrices,
+    SelectIndexRelatives,
+    SelectPriceRelatives,
+    SelectYearInversions,
+    SelectAnomalies,
+    SelectWatchlist,
+    SelectWatchSettings,
+    SelectWebhooks,
+    SelectPendingWebhookEvents,
+    SelectInflationSeries,
+    SelectInflationIndex,
+    SelectModelLifecycle,
+    SelectVehicleLifecycle,
+    SelectSearch,
+    SelectSearchBrandRefs,
+    SelectSearchModelRefs,
+    SelectSearchModelYears,
+    SelectModelDescriptions,
+    SelectBrandDescriptor,
+    SelectModelDescriptor,
+    SelectBrandCandidates,
+    SelectModelCandidates,
+    SelectAliasCheckpoint,
+    SelectAliasesSince,
+    SelectPendingAliases,
     SelectStatus,
-    SelectCount { entity: String },
-    SelectRowCount,
+    SelectHealthSummary,
+    SelectHealthIssues,
+    SelectLoadEstimates,
+    SelectObservedEstimates,
+    SelectPendingModelRefsCount,
+    SelectUnverifiedModelRefsCount,
 
     // inserts / updates
     InsertReference,
+    InsertBrandDescriptor,
     InsertBrand,
+    InsertModelDescriptor,
     InsertModel,
+    InsertVehicle,
     InsertYear,
+    UpdateYearsVerified,
+    InsertBrandYear,
+    UpdateBrandYearLoaded,
+    UpdateBrandYearsVerified,
+    UpdatePrice,
+    InsertPriceLookup,
+    InsertInflationIndex,
+    InsertFailedRequest,
+    InsertAnomaly,
+    DeleteAnomalies,
+    InsertWatch,
+    DeleteWatch,
+    UpdateWatchAlerted,
+    UpdateWatchSettings,
+    InsertWebhook,
+    DeleteWebhook,
+    InsertWebhookEvent,
+    UpdateWebhookDelivered,
+    UpdateWebhookFailed,
+    UpdateFipeCode,
+    UpdateModelAttributes,
+    InsertAlias,
+    RefreshModelLifecycle,
+    RefreshVehicleLifecycle,
+    UpdateAliasStatus,
+    UpdateCanonical { kind: String },
+    UpdateDescriptorName { kind: String },
     UpdateStatus,
-    UpdateRowCount { entity: String },
+    UpdateVehicleDescriptor,
+    InsertReloadLog,
+    DeleteReference,
+    DeleteBrandReference,
+    DeleteVehicleReference,
+    DeleteStaleLifecycle,
+    UpdateRemoteReference,
+    UpdateLoadEstimate { entity: String },
+
+    // migrations
+    SelectSchemaVersion,
+    SelectTableExists,
+    UpdateSchemaVersion { version: i32 },
+    Migrate { version: i32 },
 }
 
 impl Sql {
@@ -37,25 +160,67 @@ impl Sql {
         match self {
             Sql::DropTables =>
                 r#"
+              DROP TABLE IF EXISTS search_index;
+              DROP TABLE IF EXISTS aliases;
+              DROP TABLE IF EXISTS lifecycle;
+              DROP TABLE IF EXISTS reload_log;
+              DROP TABLE IF EXISTS brand_years;
+              DROP TABLE IF EXISTS price_lookups;
+              DROP TABLE IF EXISTS inflation_index;
+              DROP TABLE IF EXISTS failed_requests;
+              DROP TABLE IF EXISTS anomalies;
+              DROP TABLE IF EXISTS watchlist;
+              DROP TABLE IF EXISTS webhook_events;
+              DROP TABLE IF EXISTS webhooks;
+              DROP VIEW IF EXISTS years;
+              DROP VIEW IF EXISTS models;
+              DROP VIEW IF EXISTS brands;
               DROP TABLE IF EXISTS config;
-              DROP TABLE IF EXISTS years;
-              DROP TABLE IF EXISTS models;
-              DROP TABLE IF EXISTS brands;
+              DROP TABLE IF EXISTS vehicle_references;
+              DROP TABLE IF EXISTS vehicles;
+              DROP TABLE IF EXISTS model_references;
+              DROP TABLE IF EXISTS model_descriptors;
+              DROP TABLE IF EXISTS brand_references;
+              DROP TABLE IF EXISTS brand_descriptors;
               DROP TABLE IF EXISTS "references";
               DROP TABLE IF EXISTS fuels;
               DROP TABLE IF EXISTS types;
           "#.to_string(),
 
-            Sql::CreateYears =>
+            Sql::CreateVehicleReferences =>
+                r#"
+              CREATE TABLE vehicle_references(
+                  id integer PRIMARY KEY,
+                  vehicle_id integer,
+                  ref_id integer,
+                  model_ref_id integer,
+                  price_cents integer,
+                  price_raw text,
+                  foreign key(vehicle_id) references vehicles(id) ON DELETE CASCADE,
+                  foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                  foreign key(model_ref_id) references model_references(id) ON DELETE CASCADE,
+                  unique(vehicle_id, ref_id)
+              )
+          "#.to_string(),
+
+            // One row per vehicle regardless of how many references list it: model + year code
+            // (model year and fuel). The FIPE code can't be the key because FIPE only sends it
+            // with the price, after the vehicle row exists; it is filled in then (UpdateFipeCode)
+            // and code + model year + fuel lookups go through idx_vehicles_fipe_code. A code FIPE
+            // lists under two model descriptors gives two rows; lookups refuse such matches.
+            Sql::CreateVehicles =>
                 r#"
-              CREATE TABLE years(
+              CREATE TABLE vehicles(
                   id integer PRIMARY KEY,
                   description text,
                   value date,
                   fipe text,
                   model_id integer,
                   fuel_id integer,
-                  foreign key(model_id) references models(id),
+                  model_year integer,
+                  is_zero_km integer not null default 0,
+                  fipe_code text,
+                  foreign key(model_id) references model_descriptors(id),
                   foreign key(fuel_id) references fuels(id),
                   unique(fipe, model_id)
               )
@@ -63,31 +228,316 @@ impl Sql {
 
             Sql::CreateModels =>
                 r#"
-              DROP TABLE IF EXISTS models;
-              CREATE TABLE models(
+              CREATE TABLE model_descriptors(
                   id integer PRIMARY KEY,
                   description text,
                   fipe text,
                   brand_id integer,
-                  foreign key(brand_id) references brands(id),
+                  engine_displacement real,
+                  valves integer,
+                  doors integer,
+                  transmission text,
+                  turbo integer not null default 0,
+                  body_style text,
+                  fuel text,
+                  canonical_id integer,
+                  foreign key(brand_id) references brand_descriptors(id),
+                  foreign key(canonical_id) references model_descriptors(id),
                   unique(fipe, brand_id)
-              )
+              );
+              CREATE TABLE model_references(
+                  id integer PRIMARY KEY,
+                  model_id integer,
+                  ref_id integer,
+                  brand_ref_id integer,
+                  years_verified integer not null default 0,
+                  foreign key(model_id) references model_descriptors(id) ON DELETE CASCADE,
+                  foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                  foreign key(brand_ref_id) references brand_references(id) ON DELETE CASCADE,
+                  unique(model_id, ref_id)
+              );
           "#.to_string(),
 
             Sql::CreateBrands =>
                 r#"
-              CREATE TABLE brands(
+              CREATE TABLE brand_descriptors(
                   id integer PRIMARY KEY,
                   description text,
                   fipe text,
                   type_id integer,
-                  ref_id integer,
+                  canonical_id integer,
                   foreign key(type_id) references types(id),
-                  foreign key(ref_id) references "references"(id),
-                  unique(fipe, ref_id)
+                  foreign key(canonical_id) references brand_descriptors(id),
+                  unique(fipe, type_id)
+              );
+              CREATE TABLE brand_references(
+                  id integer PRIMARY KEY,
+                  brand_id integer,
+                  ref_id integer,
+                  foreign key(brand_id) references brand_descriptors(id) ON DELETE CASCADE,
+                  foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                  unique(brand_id, ref_id)
+              );
+          "#.to_string(),
+
+            // Per-reference shapes of the pre-normalisation brands / models / years tables.
+            Sql::CreateViews =>
+                r#"
+              CREATE VIEW brands AS
+              SELECT
+                  br.id AS id,
+                  bd.description AS description,
+                  bd.fipe AS fipe,
+                  bd.type_id AS type_id,
+                  br.ref_id AS ref_id
+              FROM brand_references br
+              JOIN brand_descriptors bd ON br.brand_id = bd.id;
+
+              CREATE VIEW models AS
+              SELECT
+                  mr.id AS id,
+                  md.description AS description,
+                  md.fipe AS fipe,
+                  br.id AS brand_id
+              FROM model_references mr
+              JOIN model_descriptors md ON mr.model_id = md.id
+              JOIN brand_references br ON br.brand_id = md.brand_id AND br.ref_id = mr.ref_id;
+
+              CREATE VIEW years AS
+              SELECT
+                  vr.id AS id,
+                  v.description AS description,
+                  v.value AS value,
+                  v.fipe AS fipe,
+                  mr.id AS model_id,
+                  v.fuel_id AS fuel_id,
+                  v.model_year AS model_year,
+                  v.is_zero_km AS is_zero_km
+              FROM vehicle_references vr
+              JOIN vehicles v ON vr.vehicle_id = v.id
+              JOIN model_references mr ON mr.model_id = v.model_id AND mr.ref_id = vr.ref_id;
+          "#.to_string(),
+
+            // Accent-insensitive full-text index over brand and model descriptors,
+            // kept in sync by triggers as the loaders insert descriptors.
+            Sql::CreateSearchIndex =>
+                r#"
+              CREATE VIRTUAL TABLE search_index USING fts5(
+                  brand,
+                  model,
+                  kind UNINDEXED,
+                  entity_id UNINDEXED,
+                  tokenize = 'unicode61 remove_diacritics 2'
+              );
+
+              CREATE TRIGGER brand_descriptors_search_insert AFTER INSERT ON brand_descriptors
+              BEGIN
+                  INSERT INTO search_index (brand, model, kind, entity_id)
+                  VALUES (new.description, '', 'brand', new.id);
+              END;
+
+              CREATE TRIGGER brand_descriptors_search_update AFTER UPDATE OF description ON brand_descriptors
+              BEGIN
+                  UPDATE search_index SET brand = new.description
+                  WHERE (kind = 'brand' AND entity_id = new.id)
+                  OR (kind = 'model' AND entity_id IN (SELECT id FROM model_descriptors WHERE brand_id = new.id));
+              END;
+
+              CREATE TRIGGER brand_descriptors_search_delete AFTER DELETE ON brand_descriptors
+              BEGIN
+                  DELETE FROM search_index WHERE kind = 'brand' AND entity_id = old.id;
+              END;
+
+              CREATE TRIGGER model_descriptors_search_insert AFTER INSERT ON model_descriptors
+              BEGIN
+                  INSERT INTO search_index (brand, model, kind, entity_id)
+                  SELECT bd.description, new.description, 'model', new.id
+                  FROM brand_descriptors bd
+                  WHERE bd.id = new.brand_id;
+              END;
+
+              CREATE TRIGGER model_descriptors_search_update AFTER UPDATE OF description ON model_descriptors
+              BEGIN
+                  UPDATE search_index SET model = new.description WHERE kind = 'model' AND entity_id = new.id;
+              END;
+
+              CREATE TRIGGER model_descriptors_search_delete AFTER DELETE ON model_descriptors
+              BEGIN
+                  DELETE FROM search_index WHERE kind = 'model' AND entity_id = old.id;
+              END;
+          "#.to_string(),
+
+            // Variant labels per brand / model descriptor. `entity_id` is the descriptor the
+            // label was seen on, `canonical_id` the one it maps to (the same one for code matches).
+            Sql::CreateAliases =>
+                r#"
+              CREATE TABLE aliases(
+                  id integer PRIMARY KEY,
+                  kind text,
+                  entity_id integer,
+                  canonical_id integer,
+                  label text,
+                  match text,
+                  score real,
+                  status text default 'pending',
+                  ref_id integer,
+                  detected_at date default (datetime('now', 'localtime')),
+                  foreign key(ref_id) references "references"(id) ON DELETE SET NULL,
+                  unique(kind, entity_id, canonical_id, label)
+              )
+          "#.to_string(),
+
+            // First and last reference listing each model (kind 'model', model descriptor id)
+            // and each model year (kind 'vehicle', vehicle id).
+            Sql::CreateLifecycle =>
+                r#"
+              CREATE TABLE lifecycle(
+                  id integer PRIMARY KEY,
+                  kind text,
+                  entity_id integer,
+                  first_ref_id integer,
+                  last_ref_id integer,
+                  foreign key(first_ref_id) references "references"(id) ON DELETE CASCADE,
+                  foreign key(last_ref_id) references "references"(id) ON DELETE CASCADE,
+                  unique(kind, entity_id)
+              )
+          "#.to_string(),
+
+            // Differences found by targeted reloads: kind brand / model / year,
+            // change added / alias / updated / removed (alias: a new label for a known code).
+            // Removed rows are flagged, not deleted.
+            Sql::CreateReloadLog =>
+                r#"
+              CREATE TABLE reload_log(
+                  id integer PRIMARY KEY,
+                  kind text,
+                  change text,
+                  entity_id integer,
+                  label text,
+                  ref_id integer,
+                  detected_at date default (datetime('now', 'localtime')),
+                  foreign key(ref_id) references "references"(id) ON DELETE CASCADE
+              )
+          "#.to_string(),
+
+            // Model years a brand lists in a reference ("Anos" in ConsultarModelos);
+            // loaded once its models were fetched through ConsultarModelosAtravesDoAno.
+            Sql::CreateBrandYears =>
+                r#"
+              CREATE TABLE brand_years(
+                  id integer PRIMARY KEY,
+                  brand_ref_id integer,
+                  fipe text,
+                  description text,
+                  loaded integer not null default 0,
+                  foreign key(brand_ref_id) references brand_references(id) ON DELETE CASCADE,
+                  unique(brand_ref_id, fipe)
+              )
+          "#.to_string(),
+
+            // Prices fetched by FIPE code for vehicles not loaded through the brand / model tree.
+            Sql::CreatePriceLookups =>
+                r#"
+              CREATE TABLE price_lookups(
+                  id integer PRIMARY KEY,
+                  fipe_code text,
+                  model_year integer,
+                  fuel_id integer,
+                  ref_id integer,
+                  brand text,
+                  model text,
+                  price_cents integer,
+                  price_raw text,
+                  fetched_at date default (datetime('now', 'localtime')),
+                  foreign key(fuel_id) references fuels(id),
+                  foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                  unique(fipe_code, model_year, fuel_id, ref_id)
+              )
+          "#.to_string(),
+
+            // Imported monthly index levels (IPCA, IGP-M, ...); month is the first day, like ref_date.
+            Sql::CreateInflationIndex =>
+                r#"
+              CREATE TABLE inflation_index(
+                  id integer PRIMARY KEY,
+                  series text,
+                  month date,
+                  value real,
+                  unique(series, month)
+              )
+          "#.to_string(),
+
+            // Requests whose response was rejected (undecodable JSON, malformed prices), with the
+            // request body and the offending value for auditing.
+            Sql::CreateFailedRequests =>
+                r#"
+              CREATE TABLE failed_requests(
+                  id integer PRIMARY KEY,
+                  url text,
+                  body text,
+                  error text,
+                  raw_value text,
+                  failed_at date default (datetime('now', 'localtime'))
               )
           "#.to_string(),
 
+            // Data-quality flags from the last price load: "jump" (month-over-month change far
+            // from the rest of the model's) and "year_inversion" (cheaper than an older model year).
+            Sql::CreateAnomalies =>
+                r#"
+              CREATE TABLE anomalies(
+                  id integer PRIMARY KEY,
+                  vehicle_ref_id integer,
+                  kind text,
+                  detail text,
+                  ratio real,
+                  detected_at date default (datetime('now', 'localtime')),
+                  foreign key(vehicle_ref_id) references vehicle_references(id) ON DELETE CASCADE,
+                  unique(vehicle_ref_id, kind)
+              )
+          "#.to_string(),
+
+            // Vehicles followed by buyers. `alerted_ref_id` is the last reference they were
+            // checked against, so each price change is only alerted once.
+            Sql::CreateWatchlist =>
+                r#"
+              CREATE TABLE watchlist(
+                  id integer PRIMARY KEY,
+                  fipe_code text,
+                  model_year integer,
+                  fuel_id integer,
+                  alerted_ref_id integer,
+                  added_at date default (datetime('now', 'localtime')),
+                  foreign key(fuel_id) references fuels(id),
+                  unique(fipe_code, model_year, fuel_id)
+              )
+          "#.to_string(),
+
+            // Outbound notifications. `events` is "*" or a comma separated list of event names;
+            // each emitted event is queued per subscribed webhook in `webhook_events` until delivered.
+            Sql::CreateWebhooks =>
+                r#"
+              CREATE TABLE webhooks(
+                  id integer PRIMARY KEY,
+                  url text unique,
+                  secret text,
+                  events text not null default '*',
+                  created_at date default (datetime('now', 'localtime'))
+              );
+
+              CREATE TABLE webhook_events(
+                  id integer PRIMARY KEY,
+                  webhook_id integer,
+                  event text,
+                  payload text,
+                  attempts integer not null default 0,
+                  last_error text,
+                  created_at date default (datetime('now', 'localtime')),
+                  delivered_at date,
+                  foreign key(webhook_id) references webhooks(id) ON DELETE CASCADE
+              );
+          "#.to_string(),
+
             Sql::CreateReferences =>
                 r#"
               CREATE TABLE "references"(
@@ -120,27 +570,33 @@ impl Sql {
 
             Sql::CreateIndexes =>
                 r#"
-              CREATE INDEX idx_references_id ON "references" (id);
-              CREATE INDEX idx_types_id ON types (id);
-              CREATE INDEX idx_fuels_id ON fuels (id);
-              CREATE INDEX idx_brands_ref_id ON brands (ref_id);
-              CREATE INDEX idx_brands_type_id ON brands (type_id);
-              CREATE INDEX idx_brands_id ON brands (id);
-              CREATE INDEX idx_models_brand_id ON models (brand_id);
-              CREATE INDEX idx_models_id ON models (id);
-              CREATE INDEX idx_years_model_id ON years (model_id);
-              CREATE INDEX idx_years_fuel_id ON years (fuel_id);
+              CREATE INDEX IF NOT EXISTS idx_references_id ON "references" (id);
+              CREATE INDEX IF NOT EXISTS idx_types_id ON types (id);
+              CREATE INDEX IF NOT EXISTS idx_fuels_id ON fuels (id);
+              CREATE INDEX IF NOT EXISTS idx_brand_descriptors_type_id ON brand_descriptors (type_id);
+              CREATE INDEX IF NOT EXISTS idx_brand_references_ref_id ON brand_references (ref_id);
+              CREATE INDEX IF NOT EXISTS idx_model_descriptors_brand_id ON model_descriptors (brand_id);
+              CREATE INDEX IF NOT EXISTS idx_model_references_ref_id ON model_references (ref_id);
+              CREATE INDEX IF NOT EXISTS idx_vehicles_model_id ON vehicles (model_id);
+              CREATE INDEX IF NOT EXISTS idx_vehicles_fuel_id ON vehicles (fuel_id);
+              CREATE INDEX IF NOT EXISTS idx_vehicle_references_ref_id ON vehicle_references (ref_id);
+              CREATE INDEX IF NOT EXISTS idx_vehicles_fipe_code ON vehicles (fipe_code);
+              CREATE INDEX IF NOT EXISTS idx_model_references_brand_ref_id ON model_references (brand_ref_id);
+              CREATE INDEX IF NOT EXISTS idx_vehicle_references_model_ref_id ON vehicle_references (model_ref_id);
           "#.to_string(),
 
+            Sql::EnableForeignKeys => "PRAGMA foreign_keys = ON".to_string(),
+
             Sql::CreateConfig =>
                 r#"
               CREATE TABLE config(
                   db_status text,
                   last_update date,
-                  brands_rowcount integer default 51500,
-                  models_rowcount integer default 1970128,
-                  years_rowcount integer default 8119581,
-                  vehicles_rowcount integer default 0
+                  brands_per_parent real default 60,
+                  models_per_parent real default 40,
+                  years_per_parent real default 4,
+                  latest_remote_reference text,
+                  watch_threshold real default 5
               );
 
               INSERT INTO config(db_status, last_update) VALUES ('empty', datetime('now', 'localtime'));
@@ -171,17 +627,17 @@ impl Sql {
               FROM "references" r
               WHERE NOT EXISTS (
                   SELECT 1
-                  FROM brands b
-                  WHERE b.ref_id = r.id
+                  FROM brand_references br
+                  WHERE br.ref_id = r.id
               )
               "#.to_string(),
 
             Sql::SelectBrands =>
                 r#"
               SELECT
-                  b.id AS id,
-                  b.description AS description,
-                  b.fipe AS fipe,
+                  br.id AS id,
+                  bd.description AS description,
+                  bd.fipe AS fipe,
                   r.id AS ref_id,
                   CASE strftime('%m', r.ref_date)
                     WHEN '01' THEN 'janeiro'
@@ -197,26 +653,30 @@ impl Sql {
                     WHEN '11' THEN 'novembro'
                     WHEN '12' THEN 'dezembro'
                   END || '/' || strftime('%Y', r.ref_date) AS ref_date,
-                  b.type_id AS type_id,
+                  bd.type_id AS type_id,
                   t.description type_description
-              FROM brands b
-              LEFT JOIN "references" r ON b.ref_id = r.id
-              LEFT JOIN types t ON b.type_id = t.id
+              FROM brand_references br
+              JOIN brand_descriptors bd ON br.brand_id = bd.id
+              LEFT JOIN "references" r ON br.ref_id = r.id
+              LEFT JOIN types t ON bd.type_id = t.id
               WHERE NOT EXISTS (
                   SELECT 1
-                  FROM models m
-                  WHERE m.brand_id = b.id
+                  FROM model_references mr
+                  JOIN model_descriptors md ON mr.model_id = md.id
+                  WHERE md.brand_id = br.brand_id
+                  AND mr.ref_id = br.ref_id
               )
           "#.to_string(),
 
             Sql::SelectModels =>
                 r#"
               SELECT
-                  m.id AS id,
-                  m.description AS description,
-                  m.fipe AS fipe,
-                  b.fipe AS brand_id,
-                  b.description AS brand_description,
+                  mr.id AS id,
+                  md.id AS model_id,
+                  md.description AS description,
+                  md.fipe AS fipe,
+                  bd.fipe AS brand_id,
+                  bd.description AS brand_description,
                   MAX(r.fipe) AS ref_id,
                   CASE strftime('%m', r.ref_date)
                     WHEN '01' THEN 'janeiro'
@@ -232,87 +692,2022 @@ impl Sql {
                     WHEN '11' THEN 'novembro'
                     WHEN '12' THEN 'dezembro'
                   END || '/' || strftime('%Y', r.ref_date) AS ref_date,
-                  b.type_id AS type_id,
+                  bd.type_id AS type_id,
                   t.description AS type_description
               FROM
-                  models m
-              JOIN brands b ON
-                  m.brand_id = b.id
+                  model_references mr
+              JOIN model_descriptors md ON
+                  mr.model_id = md.id
+              JOIN brand_descriptors bd ON
+                  md.brand_id = bd.id
               JOIN "references" r ON
-                  b.ref_id = r.id
+                  mr.ref_id = r.id
               JOIN types t ON
-                  b.type_id = t.id
+                  bd.type_id = t.id
               WHERE
                   NOT EXISTS (
                   SELECT
                       1
                   FROM
-                      years y
+                      vehicle_references vr
+                  JOIN vehicles v ON
+                      vr.vehicle_id = v.id
                   WHERE
-                      y.model_id = m.id
+                      v.model_id = mr.model_id
+                      AND vr.ref_id = mr.ref_id
               )
               GROUP BY
-                  m.fipe
+                  md.id
           "#.to_string(),
 
             Sql::SelectModelsReplicate =>
                 r#"
               SELECT
-              m.id AS id,
-              m.description AS description,
-              CASE strftime('%m', r.ref_date)
-                WHEN '01' THEN 'janeiro'
-                WHEN '02' THEN 'fevereiro'
-                WHEN '03' THEN 'março'
-                WHEN '04' THEN 'abril'
-                WHEN '05' THEN 'maio'
-                WHEN '06' THEN 'junho'
-                WHEN '07' THEN 'julho'
-                WHEN '08' THEN 'agosto'
-                WHEN '09' THEN 'setembro'
-                WHEN '10' THEN 'outubro'
-                WHEN '11' THEN 'novembro'
-                WHEN '12' THEN 'dezembro'
-              END || '/' || strftime('%Y', r.ref_date) AS ref_date
-              FROM models m
-              LEFT JOIN brands b on m.brand_id = b.id
-              LEFT JOIN "references" r ON b.ref_id = r.id
-              WHERE m.fipe = ?1
+              mr.id AS id,
+              md.description AS description,
+                  CASE strftime('%m', r.ref_date)
+                    WHEN '01' THEN 'janeiro'
+                    WHEN '02' THEN 'fevereiro'
+                    WHEN '03' THEN 'março'
+                    WHEN '04' THEN 'abril'
+                    WHEN '05' THEN 'maio'
+                    WHEN '06' THEN 'junho'
+                    WHEN '07' THEN 'julho'
+                    WHEN '08' THEN 'agosto'
+                    WHEN '09' THEN 'setembro'
+                    WHEN '10' THEN 'outubro'
+                    WHEN '11' THEN 'novembro'
+                    WHEN '12' THEN 'dezembro'
+                  END || '/' || strftime('%Y', r.ref_date) AS ref_date
+              FROM model_references mr
+              JOIN model_descriptors md ON mr.model_id = md.id
+              LEFT JOIN "references" r ON mr.ref_id = r.id
+              WHERE mr.model_id = ?1
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM vehicle_references vr
+                  JOIN vehicles v ON vr.vehicle_id = v.id
+                  WHERE v.model_id = mr.model_id
+                  AND vr.ref_id = mr.ref_id
+              )
+          "#.to_string(),
+
+            // Accurate years mode: one row per model reference whose years were not
+            // fetched from that reference's own table yet.
+            Sql::SelectModelsByReference =>
+                r#"
+              SELECT
+                  mr.id AS id,
+                  md.id AS model_id,
+                  md.description AS description,
+                  md.fipe AS fipe,
+                  bd.fipe AS brand_id,
+                  bd.description AS brand_description,
+                  r.fipe AS ref_id,
+                  CASE strftime('%m', r.ref_date)
+                    WHEN '01' THEN 'janeiro'
+                    WHEN '02' THEN 'fevereiro'
+                    WHEN '03' THEN 'março'
+                    WHEN '04' THEN 'abril'
+                    WHEN '05' THEN 'maio'
+                    WHEN '06' THEN 'junho'
+                    WHEN '07' THEN 'julho'
+                    WHEN '08' THEN 'agosto'
+                    WHEN '09' THEN 'setembro'
+                    WHEN '10' THEN 'outubro'
+                    WHEN '11' THEN 'novembro'
+                    WHEN '12' THEN 'dezembro'
+                  END || '/' || strftime('%Y', r.ref_date) AS ref_date,
+                  bd.type_id AS type_id,
+                  t.description AS type_description
+              FROM model_references mr
+              JOIN model_descriptors md ON mr.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              JOIN "references" r ON mr.ref_id = r.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE mr.years_verified = 0
+              ORDER BY r.ref_date DESC, md.id
+          "#.to_string(),
+
+            // Pending brand years whose brand still has models without verified years.
+            Sql::SelectBrandYears =>
+                r#"
+              SELECT
+                  bys.id AS id,
+                  bys.brand_ref_id AS brand_ref_id,
+                  bys.fipe AS fipe,
+                  bys.description AS description,
+                  bd.fipe AS brand_id,
+                  bd.description AS brand_description,
+                  r.fipe AS ref_id,
+                  CASE strftime('%m', r.ref_date)
+                    WHEN '01' THEN 'janeiro'
+                    WHEN '02' THEN 'fevereiro'
+                    WHEN '03' THEN 'março'
+                    WHEN '04' THEN 'abril'
+                    WHEN '05' THEN 'maio'
+                    WHEN '06' THEN 'junho'
+                    WHEN '07' THEN 'julho'
+                    WHEN '08' THEN 'agosto'
+                    WHEN '09' THEN 'setembro'
+                    WHEN '10' THEN 'outubro'
+                    WHEN '11' THEN 'novembro'
+                    WHEN '12' THEN 'dezembro'
+                  END || '/' || strftime('%Y', r.ref_date) AS ref_date,
+                  bd.type_id AS type_id,
+                  t.description AS type_description
+              FROM brand_years bys
+              JOIN brand_references br ON bys.brand_ref_id = br.id
+              JOIN brand_descriptors bd ON br.brand_id = bd.id
+              JOIN "references" r ON br.ref_id = r.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE bys.loaded = 0
+              AND EXISTS (
+                  SELECT 1 FROM model_references mr
+                  WHERE mr.brand_ref_id = bys.brand_ref_id AND mr.years_verified = 0
+              )
+              ORDER BY r.ref_date DESC, bd.id, bys.fipe
+          "#.to_string(),
+
+            Sql::SelectModelRefByFipe =>
+                r#"
+              SELECT mr.id
+              FROM model_references mr
+              JOIN model_descriptors md ON mr.model_id = md.id
+              WHERE mr.brand_ref_id = ?1 AND md.fipe = ?2
+          "#.to_string(),
+
+            Sql::SelectPrices =>
+                r#"
+              SELECT
+                  vr.id AS id,
+                  r.fipe AS ref_id,
+                  CASE strftime('%m', r.ref_date)
+                    WHEN '01' THEN 'janeiro'
+                    WHEN '02' THEN 'fevereiro'
+                    WHEN '03' THEN 'março'
+                    WHEN '04' THEN 'abril'
+                    WHEN '05' THEN 'maio'
+                    WHEN '06' THEN 'junho'
+                    WHEN '07' THEN 'julho'
+                    WHEN '08' THEN 'agosto'
+                    WHEN '09' THEN 'setembro'
+                    WHEN '10' THEN 'outubro'
+                    WHEN '11' THEN 'novembro'
+                    WHEN '12' THEN 'dezembro'
+                  END || '/' || strftime('%Y', r.ref_date) AS ref_date,
+                  bd.type_id AS type_id,
+                  t.description AS type_description,
+                  bd.fipe AS brand_id,
+                  bd.description AS brand_description,
+                  md.fipe AS model_id,
+                  md.description AS model_description,
+                  v.description AS description,
+                  v.model_year AS model_year,
+                  v.fuel_id AS fuel_id
+              FROM vehicle_references vr
+              JOIN vehicles v ON vr.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              JOIN "references" r ON vr.ref_id = r.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE vr.price_cents IS NULL
+              ORDER BY r.ref_date DESC
+          "#.to_string(),
+
+            Sql::SelectPricesCount =>
+                "SELECT count(id) FROM vehicle_references WHERE price_cents IS NULL".to_string(),
+
+            Sql::SelectFuelChoices => "SELECT id, description FROM fuels ORDER BY id".to_string(),
+
+            Sql::SelectBrandChoices =>
+                "SELECT id, description FROM brand_descriptors WHERE type_id = ?1 ORDER BY description".to_string(),
+
+            Sql::SelectModelChoices =>
+                "SELECT id, description FROM model_descriptors WHERE brand_id = ?1 ORDER BY description".to_string(),
+
+            Sql::SelectVehicleChoices =>
+                "SELECT id, description FROM vehicles WHERE model_id = ?1 ORDER BY model_year DESC, description".to_string(),
+
+            Sql::SelectVehicleByCode =>
+                "SELECT id FROM vehicles WHERE fipe_code = ?1 AND model_year = ?2 AND fuel_id = ?3".to_string(),
+
+            Sql::SelectLatestReference =>
+                "SELECT id FROM \"references\" ORDER BY ref_date DESC LIMIT 1".to_string(),
+
+            Sql::SelectReferenceDate => "SELECT ref_date FROM \"references\" WHERE id = ?1".to_string(),
+
+            // ?1 FIPE code, ?2 model year, ?3 fuel, ?4 reference id
+            Sql::SelectLocalPriceByCode =>
+                r#"
+              SELECT
+                  vr.id AS id,
+                  vr.price_cents / 100.0 AS price,
+                  bd.description AS brand,
+                  md.description AS model
+              FROM vehicles v
+              JOIN vehicle_references vr ON vr.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              WHERE v.fipe_code = ?1 AND v.model_year = ?2 AND v.fuel_id = ?3 AND vr.ref_id = ?4
+          "#.to_string(),
+
+            Sql::SelectCodeTypes =>
+                r#"
+              SELECT DISTINCT bd.type_id
+              FROM vehicles v
+              JOIN model_descriptors md ON v.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              WHERE v.fipe_code = ?1
+          "#.to_string(),
+
+            Sql::SelectCachedPrice =>
+                r#"
+              SELECT price_cents / 100.0 AS price, brand, model
+              FROM price_lookups
+              WHERE fipe_code = ?1 AND model_year = ?2 AND fuel_id = ?3 AND ref_id = ?4
+          "#.to_string(),
+
+            // Fleet matching; ?3 fuel is optional (NULL matches any fuel).
+            Sql::SelectFleetVehicleByCode =>
+                r#"
+              SELECT
+                  v.id AS id,
+                  bd.description AS brand,
+                  md.description AS model,
+                  v.description AS year
+              FROM vehicles v
+              JOIN model_descriptors md ON v.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              WHERE v.fipe_code = ?1 AND v.model_year = ?2 AND (?3 IS NULL OR v.fuel_id = ?3)
+              ORDER BY v.fuel_id
+          "#.to_string(),
+
+            Sql::SelectFleetVehicleByModel =>
+                r#"
+              SELECT
+                  v.id AS id,
+                  bd.description AS brand,
+                  md.description AS model,
+                  v.description AS year
+              FROM vehicles v
+              JOIN model_descriptors md ON v.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              WHERE v.model_id = ?1 AND v.model_year = ?2 AND (?3 IS NULL OR v.fuel_id = ?3)
+              ORDER BY v.fuel_id
+          "#.to_string(),
+
+            // Previous month and same month last year are matched by calendar date,
+            // so a missing reference shows up as a missing change rather than a wrong one.
+            Sql::SelectPriceHistory =>
+                r#"
+              WITH history AS (
+                  SELECT r.ref_date AS ref_date, r.fipe AS fipe, vr.price_cents / 100.0 AS price
+                  FROM vehicle_references vr
+                  JOIN "references" r ON vr.ref_id = r.id
+                  WHERE vr.vehicle_id = ?1
+                  AND vr.price_cents IS NOT NULL
+              )
+              SELECT
+                  h.ref_date AS ref_date,
+                  h.fipe AS fipe,
+                  h.price AS price,
+                  (SELECT p.price FROM history p WHERE p.ref_date = date(h.ref_date, '-1 month')) AS month_ago_price,
+                  (SELECT p.price FROM history p WHERE p.ref_date = date(h.ref_date, '-12 months')) AS year_ago_price
+              FROM history h
+              ORDER BY h.ref_date
+          "#.to_string(),
+
+            Sql::SelectReferenceChoices =>
+                r#"
+              SELECT id, strftime('%Y-%m', ref_date) || ' (' || fipe || ')' AS description
+              FROM "references"
+              ORDER BY ref_date DESC
+          "#.to_string(),
+
+            Sql::SelectReferenceBrandChoices =>
+                r#"
+              SELECT br.id AS id, bd.description AS description
+              FROM brand_references br
+              JOIN brand_descriptors bd ON br.brand_id = bd.id
+              WHERE br.ref_id = ?1
+              AND bd.type_id = ?2
+              ORDER BY bd.description
+          "#.to_string(),
+
+            Sql::SelectReferenceModelChoices =>
+                r#"
+              SELECT mr.id AS id, md.description AS description
+              FROM brand_references br
+              JOIN model_descriptors md ON md.brand_id = br.brand_id
+              JOIN model_references mr ON mr.model_id = md.id AND mr.ref_id = br.ref_id
+              WHERE br.id = ?1
+              ORDER BY md.description
+          "#.to_string(),
+
+            Sql::SelectReferenceFipe => "SELECT fipe FROM \"references\" WHERE id = ?1".to_string(),
+
+            // API keys for re-fetching one brand / model listing.
+            Sql::SelectBrandRefKeys =>
+                r#"
+              SELECT r.id AS ref_id, bd.type_id AS type_id, r.fipe AS ref_fipe, bd.fipe AS brand_fipe
+              FROM brand_references br
+              JOIN brand_descriptors bd ON br.brand_id = bd.id
+              JOIN "references" r ON br.ref_id = r.id
+              WHERE br.id = ?1
+          "#.to_string(),
+
+            Sql::SelectModelRefKeys =>
+                r#"
+              SELECT
+                  r.id AS ref_id,
+                  bd.type_id AS type_id,
+                  r.fipe AS ref_fipe,
+                  bd.fipe AS brand_fipe,
+                  md.fipe AS model_fipe
+              FROM model_references mr
+              JOIN model_descriptors md ON mr.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              JOIN "references" r ON mr.ref_id = r.id
+              WHERE mr.id = ?1
+          "#.to_string(),
+
+            // What a reference (?1, type ?2) / brand listing / model listing currently holds.
+            Sql::SelectReloadBrands =>
+                r#"
+              SELECT br.id AS id, bd.fipe AS fipe, bd.description AS description
+              FROM brand_references br
+              JOIN brand_descriptors bd ON br.brand_id = bd.id
+              WHERE br.ref_id = ?1
+              AND bd.type_id = ?2
+          "#.to_string(),
+
+            Sql::SelectReloadModels =>
+                r#"
+              SELECT mr.id AS id, md.fipe AS fipe, md.description AS description
+              FROM brand_references br
+              JOIN model_descriptors md ON md.brand_id = br.brand_id
+              JOIN model_references mr ON mr.model_id = md.id AND mr.ref_id = br.ref_id
+              WHERE br.id = ?1
+          "#.to_string(),
+
+            Sql::SelectReloadYears =>
+                r#"
+              SELECT vr.id AS id, v.fipe AS fipe, v.description AS description
+              FROM model_references mr
+              JOIN vehicles v ON v.model_id = mr.model_id
+              JOIN vehicle_references vr ON vr.vehicle_id = v.id AND vr.ref_id = mr.ref_id
+              WHERE mr.id = ?1
+          "#.to_string(),
+
+            Sql::SelectVehicleDescriptor =>
+                r#"
+              SELECT v.id AS id, v.description AS description, v.value AS value
+              FROM model_references mr
+              JOIN vehicles v ON v.model_id = mr.model_id
+              WHERE v.fipe = ?1
+              AND mr.id = ?2
+          "#.to_string(),
+
+            Sql::SelectReloadLogCheckpoint => "SELECT COALESCE(MAX(id), 0) FROM reload_log".to_string(),
+
+            Sql::SelectReloadLogSince =>
+                r#"
+              SELECT
+                  l.kind AS kind,
+                  l.change AS change,
+                  strftime('%Y-%m', r.ref_date) AS ref_date,
+                  l.label AS label
+              FROM reload_log l
+              LEFT JOIN "references" r ON l.ref_id = r.id
+              WHERE l.id > ?1
+              ORDER BY l.id
+          "#.to_string(),
+
+            // Diff queries: rows listed in reference ?1 but not in reference ?2.
+            Sql::SelectDiffBrands =>
+                r#"
+              SELECT DISTINCT
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  NULL AS model,
+                  NULL AS year
+              FROM brand_references b1
+              JOIN brand_descriptors bd ON b1.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE b1.ref_id = ?1
+              AND NOT EXISTS (
+                  SELECT 1
+                  FROM brand_references b2
+                  JOIN brand_descriptors bd2 ON b2.brand_id = bd2.id
+                  WHERE b2.ref_id = ?2
+                  AND COALESCE(bd2.canonical_id, bd2.id) = COALESCE(bd.canonical_id, bd.id)
+              )
+              ORDER BY t.id, brand
+          "#.to_string(),
+
+            Sql::SelectDiffModels =>
+                r#"
+              SELECT DISTINCT
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  COALESCE(cm.description, md.description) AS model,
+                  NULL AS year
+              FROM model_references m1
+              JOIN model_descriptors md ON m1.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE m1.ref_id = ?1
               AND NOT EXISTS (
                   SELECT 1
-                  FROM years y
-                  WHERE m.id = y.model_id
+                  FROM model_references m2
+                  JOIN model_descriptors md2 ON m2.model_id = md2.id
+                  WHERE m2.ref_id = ?2
+                  AND COALESCE(md2.canonical_id, md2.id) = COALESCE(md.canonical_id, md.id)
+              )
+              ORDER BY t.id, brand, model
+          "#.to_string(),
+
+            Sql::SelectDiffVehicles =>
+                r#"
+              SELECT
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  COALESCE(cm.description, md.description) AS model,
+                  v.description AS year
+              FROM vehicle_references v1
+              JOIN vehicles v ON v1.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE v1.ref_id = ?1
+              AND NOT EXISTS (
+                  SELECT 1 FROM vehicle_references v2 WHERE v2.ref_id = ?2 AND v2.vehicle_id = v1.vehicle_id
+              )
+              ORDER BY t.id, brand, model, v.model_year
+          "#.to_string(),
+
+            Sql::SelectDiffPrices =>
+                r#"
+              SELECT
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  COALESCE(cm.description, md.description) AS model,
+                  v.description AS year,
+                  v1.price_cents / 100.0 AS old_price,
+                  v2.price_cents / 100.0 AS new_price
+              FROM vehicle_references v1
+              JOIN vehicle_references v2 ON v2.vehicle_id = v1.vehicle_id AND v2.ref_id = ?2
+              JOIN vehicles v ON v1.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE v1.ref_id = ?1
+              AND v1.price_cents IS NOT NULL
+              AND v2.price_cents IS NOT NULL
+              AND v1.price_cents <> v2.price_cents
+              ORDER BY t.id, brand, model, v.model_year
+          "#.to_string(),
+
+            // Optional filters: a NULL parameter matches everything.
+            Sql::SelectDepreciationPrices =>
+                r#"
+              SELECT
+                  vr.ref_id AS ref_id,
+                  CAST(strftime('%Y', r.ref_date) AS integer) AS ref_year,
+                  t.id AS type_id,
+                  t.description AS type_description,
+                  COALESCE(bd.canonical_id, bd.id) AS brand_id,
+                  COALESCE(cb.description, bd.description) AS brand_description,
+                  COALESCE(md.canonical_id, md.id) AS model_id,
+                  COALESCE(cm.description, md.description) AS model_description,
+                  v.fuel_id AS fuel_id,
+                  v.model_year AS model_year,
+                  v.is_zero_km AS is_zero_km,
+                  vr.price_cents / 100.0 AS price
+              FROM vehicle_references vr
+              JOIN "references" r ON vr.ref_id = r.id
+              JOIN vehicles v ON vr.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE vr.price_cents IS NOT NULL
+              AND (?1 IS NULL OR vr.ref_id = ?1)
+              AND (?2 IS NULL OR bd.type_id = ?2)
+              AND (?3 IS NULL OR COALESCE(bd.canonical_id, bd.id) = ?3)
+          "#.to_string(),
+
+            // Price relatives of vehicles priced in two consecutive reference months, for type ?1.
+            Sql::SelectIndexRelatives =>
+                r#"
+              WITH priced AS (
+                  SELECT vr.vehicle_id AS vehicle_id, r.ref_date AS ref_date, vr.price_cents / 100.0 AS price
+                  FROM vehicle_references vr
+                  JOIN "references" r ON vr.ref_id = r.id
+                  WHERE vr.price_cents > 0
+              )
+              SELECT
+                  strftime('%Y-%m', cur.ref_date) AS ref_month,
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand_description,
+                  COALESCE(f.description, '-') AS fuel_description,
+                  cur.price / prev.price AS relative
+              FROM priced cur
+              JOIN priced prev ON prev.vehicle_id = cur.vehicle_id
+                  AND prev.ref_date = date(cur.ref_date, '-1 month')
+              JOIN vehicles v ON cur.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              LEFT JOIN fuels f ON v.fuel_id = f.id
+              WHERE (?1 IS NULL OR bd.type_id = ?1)
+              ORDER BY ref_month
+          "#.to_string(),
+
+            // Month-over-month price relative of every vehicle priced in consecutive months.
+            Sql::SelectPriceRelatives =>
+                r#"
+              WITH priced AS (
+                  SELECT vr.id AS id, vr.vehicle_id AS vehicle_id, r.ref_date AS ref_date, vr.price_cents AS price_cents
+                  FROM vehicle_references vr
+                  JOIN "references" r ON vr.ref_id = r.id
+                  WHERE vr.price_cents > 0
+              )
+              SELECT
+                  cur.id AS id,
+                  v.model_id AS model_id,
+                  strftime('%Y-%m', cur.ref_date) AS ref_month,
+                  prev.price_cents / 100.0 AS old_price,
+                  cur.price_cents / 100.0 AS new_price
+              FROM priced cur
+              JOIN priced prev ON prev.vehicle_id = cur.vehicle_id
+                  AND prev.ref_date = date(cur.ref_date, '-1 month')
+              JOIN vehicles v ON cur.vehicle_id = v.id
+          "#.to_string(),
+
+            // Vehicles priced below the next older model year of the same model and fuel in the
+            // same reference.
+            Sql::SelectYearInversions =>
+                r#"
+              WITH priced AS (
+                  SELECT
+                      vr.id AS id,
+                      v.model_year AS model_year,
+                      vr.price_cents AS price_cents,
+                      LAG(v.model_year) OVER w AS older_year,
+                      LAG(vr.price_cents) OVER w AS older_cents
+                  FROM vehicle_references vr
+                  JOIN vehicles v ON vr.vehicle_id = v.id
+                  WHERE vr.price_cents > 0
+                  WINDOW w AS (PARTITION BY vr.ref_id, v.model_id, v.fuel_id ORDER BY v.model_year)
+              )
+              SELECT
+                  id,
+                  model_year,
+                  older_year,
+                  price_cents / 100.0 AS price,
+                  older_cents / 100.0 AS older_price
+              FROM priced
+              WHERE price_cents < older_cents
+          "#.to_string(),
+
+            Sql::SelectAnomalies =>
+                r#"
+              SELECT
+                  r.ref_date AS ref_date,
+                  a.kind AS kind,
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand_description,
+                  COALESCE(cm.description, md.description) AS model_description,
+                  v.description AS year_description,
+                  v.fipe_code AS fipe_code,
+                  a.detail AS detail,
+                  a.ratio AS ratio
+              FROM anomalies a
+              JOIN vehicle_references vr ON a.vehicle_ref_id = vr.id
+              JOIN "references" r ON vr.ref_id = r.id
+              JOIN vehicles v ON vr.vehicle_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              ORDER BY r.ref_date DESC, a.kind, t.id, brand_description, model_description, v.model_year DESC
+          "#.to_string(),
+
+            // Every watched vehicle with its prices in the two latest references listing it (NULL until
+            // loaded). A code renamed across references maps to more than one vehicle row.
+            Sql::SelectWatchlist =>
+                r#"
+              WITH listed AS (
+                  SELECT
+                      w.id AS watch_id,
+                      MAX(v.model_id) AS model_id,
+                      r.id AS ref_id,
+                      r.ref_date AS ref_date,
+                      MAX(vr.price_cents) AS price_cents
+                  FROM watchlist w
+                  JOIN vehicles v ON v.fipe_code = w.fipe_code
+                      AND v.model_year = w.model_year
+                      AND v.fuel_id = w.fuel_id
+                  JOIN vehicle_references vr ON vr.vehicle_id = v.id AND vr.price_cents > 0
+                  JOIN "references" r ON vr.ref_id = r.id
+                  GROUP BY w.id, r.id
+              ),
+              priced AS (
+                  SELECT *, ROW_NUMBER() OVER (PARTITION BY watch_id ORDER BY ref_date DESC) AS n
+                  FROM listed
+              )
+              SELECT
+                  w.id AS id,
+                  w.fipe_code AS fipe_code,
+                  w.model_year AS model_year,
+                  COALESCE(f.description, '-') AS fuel_description,
+                  COALESCE(cb.description, bd.description) AS brand_description,
+                  COALESCE(cm.description, md.description) AS model_description,
+                  w.alerted_ref_id AS alerted_ref_id,
+                  cur.ref_id AS ref_id,
+                  cur.ref_date AS ref_date,
+                  cur.price_cents / 100.0 AS new_price,
+                  prev.ref_date AS old_ref_date,
+                  prev.price_cents / 100.0 AS old_price
+              FROM watchlist w
+              LEFT JOIN fuels f ON w.fuel_id = f.id
+              LEFT JOIN priced cur ON cur.watch_id = w.id AND cur.n = 1
+              LEFT JOIN priced prev ON prev.watch_id = w.id AND prev.n = 2
+              LEFT JOIN model_descriptors md ON cur.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              LEFT JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              ORDER BY brand_description, model_description, w.fipe_code, w.model_year
+          "#.to_string(),
+
+            Sql::SelectWatchSettings =>
+                "SELECT watch_threshold FROM config".to_string(),
+
+            Sql::SelectWebhooks =>
+                "SELECT id, url, secret, events FROM webhooks ORDER BY id".to_string(),
+
+            // Undelivered events with fewer than ?1 attempts, oldest first.
+            Sql::SelectPendingWebhookEvents =>
+                r#"
+              SELECT
+                  e.id AS id,
+                  e.webhook_id AS webhook_id,
+                  e.event AS event,
+                  e.payload AS payload,
+                  w.url AS url,
+                  w.secret AS secret
+              FROM webhook_events e
+              JOIN webhooks w ON e.webhook_id = w.id
+              WHERE e.delivered_at IS NULL AND e.attempts < ?1
+              ORDER BY e.id
+          "#.to_string(),
+
+            Sql::SelectInflationSeries =>
+                "SELECT MIN(id) AS id, series AS description FROM inflation_index GROUP BY series ORDER BY series".to_string(),
+
+            Sql::SelectInflationIndex =>
+                "SELECT strftime('%Y-%m', month) AS month, value FROM inflation_index WHERE series = ?1".to_string(),
+
+            // Launches (first listed) and discontinuations (first loaded reference no longer
+            // listing it) dated between references ?1 and ?2, for type ?3 and optional brand ?4.
+            // Anything already listed in the earliest loaded reference is not a launch.
+            Sql::SelectModelLifecycle =>
+                r#"
+              WITH loaded AS (
+                  SELECT DISTINCT r.id AS id, r.ref_date AS ref_date
+                  FROM model_references m
+                  JOIN "references" r ON m.ref_id = r.id
+              ),
+              events AS (
+                  SELECT l.entity_id AS entity_id, 'launch' AS event, f.ref_date AS event_date
+                  FROM lifecycle l
+                  JOIN "references" f ON l.first_ref_id = f.id
+                  WHERE l.kind = 'model'
+                  AND f.ref_date > (SELECT MIN(ref_date) FROM loaded)
+                  UNION ALL
+                  SELECT
+                      l.entity_id,
+                      'discontinued',
+                      (SELECT MIN(ld.ref_date) FROM loaded ld WHERE ld.ref_date > s.ref_date)
+                  FROM lifecycle l
+                  JOIN "references" s ON l.last_ref_id = s.id
+                  WHERE l.kind = 'model'
+              )
+              SELECT
+                  e.event AS event,
+                  'model' AS kind,
+                  strftime('%Y-%m', e.event_date) AS ref_date,
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  md.description AS model,
+                  NULL AS year
+              FROM events e
+              JOIN model_descriptors md ON e.entity_id = md.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE e.event_date BETWEEN (SELECT ref_date FROM "references" WHERE id = ?1)
+                  AND (SELECT ref_date FROM "references" WHERE id = ?2)
+              AND bd.type_id = ?3
+              AND (?4 IS NULL OR COALESCE(bd.canonical_id, bd.id) = ?4)
+              ORDER BY e.event_date, brand, model
+          "#.to_string(),
+
+            Sql::SelectVehicleLifecycle =>
+                r#"
+              WITH loaded AS (
+                  SELECT DISTINCT r.id AS id, r.ref_date AS ref_date
+                  FROM vehicle_references m
+                  JOIN "references" r ON m.ref_id = r.id
+              ),
+              events AS (
+                  SELECT l.entity_id AS entity_id, 'launch' AS event, f.ref_date AS event_date
+                  FROM lifecycle l
+                  JOIN "references" f ON l.first_ref_id = f.id
+                  WHERE l.kind = 'vehicle'
+                  AND f.ref_date > (SELECT MIN(ref_date) FROM loaded)
+                  UNION ALL
+                  SELECT
+                      l.entity_id,
+                      'discontinued',
+                      (SELECT MIN(ld.ref_date) FROM loaded ld WHERE ld.ref_date > s.ref_date)
+                  FROM lifecycle l
+                  JOIN "references" s ON l.last_ref_id = s.id
+                  WHERE l.kind = 'vehicle'
+              )
+              SELECT
+                  e.event AS event,
+                  'vehicle' AS kind,
+                  strftime('%Y-%m', e.event_date) AS ref_date,
+                  t.description AS type_description,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  COALESCE(cm.description, md.description) AS model,
+                  v.description AS year
+              FROM events e
+              JOIN vehicles v ON e.entity_id = v.id
+              JOIN model_descriptors md ON v.model_id = md.id
+              LEFT JOIN model_descriptors cm ON md.canonical_id = cm.id
+              JOIN brand_descriptors bd ON md.brand_id = bd.id
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              WHERE e.event_date BETWEEN (SELECT ref_date FROM "references" WHERE id = ?1)
+                  AND (SELECT ref_date FROM "references" WHERE id = ?2)
+              AND bd.type_id = ?3
+              AND (?4 IS NULL OR COALESCE(bd.canonical_id, bd.id) = ?4)
+              ORDER BY e.event_date, brand, model
+          "#.to_string(),
+
+            Sql::SelectSearch =>
+                r#"
+              WITH hits AS (
+                  SELECT
+                      s.kind AS kind,
+                      CASE s.kind
+                          WHEN 'brand' THEN (
+                              SELECT COALESCE(bd.canonical_id, bd.id) FROM brand_descriptors bd WHERE bd.id = s.entity_id
+                          )
+                          ELSE (
+                              SELECT COALESCE(md.canonical_id, md.id) FROM model_descriptors md WHERE md.id = s.entity_id
+                          )
+                      END AS entity_id,
+                      bm25(search_index) AS rank
+                  FROM search_index s
+                  WHERE search_index MATCH ?1
               )
+              SELECT
+                  h.kind AS kind,
+                  h.entity_id AS entity_id,
+                  COALESCE(cb.description, bd.description) AS brand,
+                  md.description AS model,
+                  t.description AS type_description
+              FROM hits h
+              LEFT JOIN model_descriptors md ON h.kind = 'model' AND md.id = h.entity_id
+              JOIN brand_descriptors bd ON bd.id = CASE h.kind WHEN 'brand' THEN h.entity_id ELSE md.brand_id END
+              LEFT JOIN brand_descriptors cb ON bd.canonical_id = cb.id
+              JOIN types t ON bd.type_id = t.id
+              GROUP BY h.kind, h.entity_id
+              ORDER BY MIN(h.rank)
+              LIMIT ?2
+          "#.to_string(),
+
+            Sql::SelectSearchBrandRefs =>
+                r#"
+              SELECT
+                  strftime('%Y-%m', MIN(r.ref_date)) AS first_ref,
+                  strftime('%Y-%m', MAX(r.ref_date)) AS last_ref,
+                  COUNT(DISTINCT r.id) AS ref_count
+              FROM brand_references br
+              JOIN brand_descriptors bd ON br.brand_id = bd.id
+              JOIN "references" r ON br.ref_id = r.id
+              WHERE COALESCE(bd.canonical_id, bd.id) = ?1
+          "#.to_string(),
+
+            Sql::SelectSearchModelRefs =>
+                r#"
+              SELECT
+                  strftime('%Y-%m', MIN(r.ref_date)) AS first_ref,
+                  strftime('%Y-%m', MAX(r.ref_date)) AS last_ref,
+                  COUNT(DISTINCT r.id) AS ref_count
+              FROM model_references mr
+              JOIN model_descriptors md ON mr.model_id = md.id
+              JOIN "references" r ON mr.ref_id = r.id
+              WHERE COALESCE(md.canonical_id, md.id) = ?1
+          "#.to_string(),
+
+            Sql::SelectSearchModelYears =>
+                r#"
+              SELECT DISTINCT v.model_year AS model_year, v.is_zero_km AS is_zero_km
+              FROM vehicles v
+              JOIN model_descriptors md ON v.model_id = md.id
+              WHERE COALESCE(md.canonical_id, md.id) = ?1
+              ORDER BY v.model_year
           "#.to_string(),
 
-            Sql::SelectStatus => "SELECT db_status, last_update FROM config".to_string(),
+            Sql::SelectModelDescriptions => "SELECT id, description FROM model_descriptors".to_string(),
 
-            Sql::SelectCount { entity } => format!("SELECT count(id) FROM {}", entity),
+            Sql::SelectBrandDescriptor =>
+                "SELECT id, description FROM brand_descriptors WHERE fipe = ?1 AND type_id = ?2".to_string(),
 
-            Sql::SelectRowCount =>
-                "SELECT
-              brands_rowcount,
-              models_rowcount,
-              years_rowcount,
-              vehicles_rowcount
-          FROM config".to_string(),
+            Sql::SelectModelDescriptor =>
+                r#"
+              SELECT md.id AS id, md.description AS description
+              FROM model_descriptors md
+              JOIN brand_references br ON md.brand_id = br.brand_id
+              WHERE md.fipe = ?1
+              AND br.id = ?2
+          "#.to_string(),
+
+            // Fuzzy alias candidates: same parent, not already an alias, not listed in reference ?2.
+            Sql::SelectBrandCandidates =>
+                r#"
+              SELECT bd.id AS id, bd.description AS description
+              FROM brand_descriptors bd
+              WHERE bd.type_id = (SELECT type_id FROM brand_descriptors WHERE id = ?1)
+              AND bd.id <> ?1
+              AND bd.canonical_id IS NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM brand_references br WHERE br.brand_id = bd.id AND br.ref_id = ?2
+              )
+          "#.to_string(),
+
+            Sql::SelectModelCandidates =>
+                r#"
+              SELECT md.id AS id, md.description AS description
+              FROM model_descriptors md
+              WHERE md.brand_id = (SELECT brand_id FROM model_descriptors WHERE id = ?1)
+              AND md.id <> ?1
+              AND md.canonical_id IS NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM model_references mr WHERE mr.model_id = md.id AND mr.ref_id = ?2
+              )
+          "#.to_string(),
+
+            Sql::SelectAliasCheckpoint => "SELECT COALESCE(MAX(id), 0) FROM aliases".to_string(),
+
+            Sql::SelectAliasesSince =>
+                r#"
+              SELECT
+                  a.id AS id,
+                  a.kind AS kind,
+                  a.entity_id AS entity_id,
+                  a.canonical_id AS canonical_id,
+                  COALESCE(bd.description, md.description) AS canonical,
+                  a.label AS label,
+                  a.match AS match,
+                  a.score AS score,
+                  a.status AS status,
+                  strftime('%Y-%m', r.ref_date) AS ref_date
+              FROM aliases a
+              LEFT JOIN brand_descriptors bd ON a.kind = 'brand' AND bd.id = a.canonical_id
+              LEFT JOIN model_descriptors md ON a.kind = 'model' AND md.id = a.canonical_id
+              LEFT JOIN "references" r ON a.ref_id = r.id
+              WHERE a.id > ?1
+              ORDER BY a.id
+          "#.to_string(),
+
+            Sql::SelectPendingAliases =>
+                r#"
+              SELECT
+                  a.id AS id,
+                  a.kind AS kind,
+                  a.entity_id AS entity_id,
+                  a.canonical_id AS canonical_id,
+                  COALESCE(bd.description, md.description) AS canonical,
+                  a.label AS label,
+                  a.match AS match,
+                  a.score AS score,
+                  a.status AS status,
+                  strftime('%Y-%m', r.ref_date) AS ref_date
+              FROM aliases a
+              LEFT JOIN brand_descriptors bd ON a.kind = 'brand' AND bd.id = a.canonical_id
+              LEFT JOIN model_descriptors md ON a.kind = 'model' AND md.id = a.canonical_id
+              LEFT JOIN "references" r ON a.ref_id = r.id
+              WHERE a.status = 'pending'
+              AND a.id > ?1
+              ORDER BY a.kind, canonical
+          "#.to_string(),
+
+            Sql::SelectStatus =>
+                "SELECT db_status, last_update, latest_remote_reference FROM config".to_string(),
+
+            // Per reference × type: brands listed, brands with models, models, models with years.
+            Sql::SelectHealthSummary =>
+                r#"
+              WITH brand_loads AS (
+                  SELECT
+                      br.ref_id AS ref_id,
+                      bd.type_id AS type_id,
+                      br.brand_id AS brand_id,
+                      EXISTS (
+                          SELECT 1
+                          FROM model_references mr
+                          JOIN model_descriptors md ON mr.model_id = md.id
+                          WHERE mr.ref_id = br.ref_id
+                          AND md.brand_id = br.brand_id
+                      ) AS has_models
+                  FROM brand_references br
+                  JOIN brand_descriptors bd ON br.brand_id = bd.id
+              ),
+              model_loads AS (
+                  SELECT
+                      mr.ref_id AS ref_id,
+                      bd.type_id AS type_id,
+                      mr.model_id AS model_id,
+                      EXISTS (
+                          SELECT 1
+                          FROM vehicle_references vr
+                          JOIN vehicles v ON vr.vehicle_id = v.id
+                          WHERE vr.ref_id = mr.ref_id
+                          AND v.model_id = mr.model_id
+                      ) AS has_years
+                  FROM model_references mr
+                  JOIN model_descriptors md ON mr.model_id = md.id
+                  JOIN brand_descriptors bd ON md.brand_id = bd.id
+              ),
+              brand_counts AS (
+                  SELECT ref_id, type_id, COUNT(*) AS brands, SUM(has_models) AS brands_with_models
+                  FROM brand_loads
+                  GROUP BY ref_id, type_id
+              ),
+              model_counts AS (
+                  SELECT ref_id, type_id, COUNT(*) AS models, SUM(has_years) AS models_with_years
+                  FROM model_loads
+                  GROUP BY ref_id, type_id
+              )
+              SELECT
+                  strftime('%Y-%m', r.ref_date) AS ref_date,
+                  t.description AS type_description,
+                  COALESCE(bc.brands, 0) AS brands,
+                  COALESCE(bc.brands_with_models, 0) AS brands_with_models,
+                  COALESCE(mc.models, 0) AS models,
+                  COALESCE(mc.models_with_years, 0) AS models_with_years
+              FROM "references" r
+              CROSS JOIN types t
+              LEFT JOIN brand_counts bc ON bc.ref_id = r.id AND bc.type_id = t.id
+              LEFT JOIN model_counts mc ON mc.ref_id = r.id AND mc.type_id = t.id
+              ORDER BY r.ref_date DESC, t.id
+          "#.to_string(),
+
+            Sql::SelectHealthIssues =>
+                r#"
+              WITH brand_loads AS (
+                  SELECT
+                      br.ref_id AS ref_id,
+                      bd.type_id AS type_id,
+                      br.brand_id AS brand_id,
+                      EXISTS (
+                          SELECT 1
+                          FROM model_references mr
+                          JOIN model_descriptors md ON mr.model_id = md.id
+                          WHERE mr.ref_id = br.ref_id
+                          AND md.brand_id = br.brand_id
+                      ) AS has_models
+                  FROM brand_references br
+                  JOIN brand_descriptors bd ON br.brand_id = bd.id
+              ),
+              model_loads AS (
+                  SELECT
+                      mr.ref_id AS ref_id,
+                      bd.type_id AS type_id,
+                      mr.model_id AS model_id,
+                      EXISTS (
+                          SELECT 1
+                          FROM vehicle_references vr
+                          JOIN vehicles v ON vr.vehicle_id = v.id
+                          WHERE vr.ref_id = mr.ref_id
+                          AND v.model_id = mr.model_id
+                      ) AS has_years
+                  FROM model_references mr
+                  JOIN model_descriptors md ON mr.model_id = md.id
+                  JOIN brand_descriptors bd ON md.brand_id = bd.id
+              ),
+              issues AS (
+                  -- Partially loaded parents: siblings in the same reference and type have children.
+                  SELECT 'brand without models' AS issue, b.ref_id AS ref_id, b.brand_id AS brand_id, NULL AS model_id, NULL AS vehicle_id
+                  FROM brand_loads b
+                  WHERE b.has_models = 0
+                  AND (b.ref_id, b.type_id) IN (SELECT ref_id, type_id FROM brand_loads WHERE has_models = 1)
+                  UNION ALL
+                  SELECT 'model without years', m.ref_id, NULL, m.model_id, NULL
+                  FROM model_loads m
+                  WHERE m.has_years = 0
+                  AND (m.ref_id, m.type_id) IN (SELECT ref_id, type_id FROM model_loads WHERE has_years = 1)
+                  UNION ALL
+                  -- Orphans: listed in a reference that does not list their parent.
+                  SELECT 'orphan model', mr.ref_id, NULL, mr.model_id, NULL
+                  FROM model_references mr
+                  JOIN model_descriptors md ON mr.model_id = md.id
+                  WHERE NOT EXISTS (
+                      SELECT 1 FROM brand_references br WHERE br.brand_id = md.brand_id AND br.ref_id = mr.ref_id
+                  )
+                  UNION ALL
+                  SELECT 'orphan year', vr.ref_id, NULL, NULL, vr.vehicle_id
+                  FROM vehicle_references vr
+                  JOIN vehicles v ON vr.vehicle_id = v.id
+                  WHERE NOT EXISTS (
+                      SELECT 1 FROM model_references mr WHERE mr.model_id = v.model_id AND mr.ref_id = vr.ref_id
+                  )
+                  UNION ALL
+                  -- Descriptors no reference lists any more.
+                  SELECT 'unreferenced brand', NULL, bd.id, NULL, NULL
+                  FROM brand_descriptors bd
+                  WHERE NOT EXISTS (SELECT 1 FROM brand_references br WHERE br.brand_id = bd.id)
+                  UNION ALL
+                  SELECT 'unreferenced model', NULL, NULL, md.id, NULL
+                  FROM model_descriptors md
+                  WHERE NOT EXISTS (SELECT 1 FROM model_references mr WHERE mr.model_id = md.id)
+                  UNION ALL
+                  SELECT 'unreferenced year', NULL, NULL, NULL, v.id
+                  FROM vehicles v
+                  WHERE NOT EXISTS (SELECT 1 FROM vehicle_references vr WHERE vr.vehicle_id = v.id)
+              )
+              SELECT
+                  i.issue AS issue,
+                  strftime('%Y-%m', r.ref_date) AS ref_date,
+                  t.description AS type_description,
+                  bd.description AS brand,
+                  md.description AS model,
+                  v.description AS year
+              FROM issues i
+              LEFT JOIN "references" r ON i.ref_id = r.id
+              LEFT JOIN vehicles v ON i.vehicle_id = v.id
+              LEFT JOIN model_descriptors md ON md.id = COALESCE(i.model_id, v.model_id)
+              LEFT JOIN brand_descriptors bd ON bd.id = COALESCE(i.brand_id, md.brand_id)
+              LEFT JOIN types t ON bd.type_id = t.id
+              ORDER BY i.issue, r.ref_date DESC, bd.description, md.description
+          "#.to_string(),
+
+            Sql::SelectLoadEstimates =>
+                "SELECT brands_per_parent, models_per_parent, years_per_parent FROM config".to_string(),
+
+            // Rows per parent in what is already loaded: brands per reference × type,
+            // models per brand per reference, years per model per reference.
+            Sql::SelectObservedEstimates =>
+                r#"
+              SELECT
+                  (
+                      SELECT CAST(COUNT(*) AS real) / NULLIF(COUNT(DISTINCT br.ref_id || '-' || bd.type_id), 0)
+                      FROM brand_references br
+                      JOIN brand_descriptors bd ON br.brand_id = bd.id
+                  ) AS brands_per_parent,
+                  (
+                      SELECT CAST(COUNT(*) AS real) / NULLIF(COUNT(DISTINCT mr.ref_id || '-' || md.brand_id), 0)
+                      FROM model_references mr
+                      JOIN model_descriptors md ON mr.model_id = md.id
+                  ) AS models_per_parent,
+                  (
+                      SELECT CAST(COUNT(*) AS real) / NULLIF(COUNT(DISTINCT vr.ref_id || '-' || v.model_id), 0)
+                      FROM vehicle_references vr
+                      JOIN vehicles v ON vr.vehicle_id = v.id
+                  ) AS years_per_parent
+          "#.to_string(),
+
+            Sql::SelectPendingModelRefsCount =>
+                r#"
+              SELECT COUNT(*)
+              FROM model_references mr
+              WHERE NOT EXISTS (
+                  SELECT 1
+                  FROM vehicle_references vr
+                  JOIN vehicles v ON vr.vehicle_id = v.id
+                  WHERE v.model_id = mr.model_id
+                  AND vr.ref_id = mr.ref_id
+              )
+          "#.to_string(),
+
+            Sql::SelectUnverifiedModelRefsCount =>
+                "SELECT COUNT(*) FROM model_references WHERE years_verified = 0".to_string(),
 
             Sql::InsertReference =>
                 "INSERT INTO \"references\" (ref_date, fipe) VALUES (?1, ?2)".to_string(),
 
+            Sql::InsertBrandDescriptor =>
+                "INSERT OR IGNORE INTO brand_descriptors (description, fipe, type_id) VALUES (?1, ?2, ?3)".to_string(),
+
             Sql::InsertBrand =>
-                "INSERT INTO brands (description, fipe, type_id, ref_id) VALUES (?1, ?2, ?3, ?4)".to_string(),
+                r#"
+              INSERT INTO brand_references (brand_id, ref_id)
+              SELECT id, ?3 FROM brand_descriptors WHERE fipe = ?1 AND type_id = ?2
+          "#.to_string(),
+
+            Sql::InsertModelDescriptor =>
+                r#"
+              INSERT OR IGNORE INTO model_descriptors (
+                  description, fipe, brand_id, engine_displacement, valves, doors, transmission, turbo, body_style, fuel
+              )
+              SELECT ?1, ?2, br.brand_id, ?4, ?5, ?6, ?7, ?8, ?9, ?10 FROM brand_references br WHERE br.id = ?3
+          "#.to_string(),
 
             Sql::InsertModel =>
-                "INSERT INTO models (description, fipe, brand_id) VALUES (?1, ?2, ?3)".to_string(),
+                r#"
+              INSERT INTO model_references (model_id, ref_id, brand_ref_id)
+              SELECT md.id, br.ref_id, br.id
+              FROM brand_references br
+              JOIN model_descriptors md ON md.brand_id = br.brand_id AND md.fipe = ?1
+              WHERE br.id = ?2
+          "#.to_string(),
+
+            Sql::InsertVehicle =>
+                r#"
+              INSERT OR IGNORE INTO vehicles (description, value, fipe, model_id, fuel_id, model_year, is_zero_km)
+              SELECT ?1, ?2, ?3, mr.model_id, ?5, ?6, ?7 FROM model_references mr WHERE mr.id = ?4
+          "#.to_string(),
 
             Sql::InsertYear =>
-                "INSERT INTO years (description, value, fipe, model_id, fuel_id) VALUES (?1, ?2, ?3, ?4, ?5)".to_string(),
+                r#"
+              INSERT INTO vehicle_references (vehicle_id, ref_id, model_ref_id)
+              SELECT v.id, mr.ref_id, mr.id
+              FROM model_references mr
+              JOIN vehicles v ON v.model_id = mr.model_id AND v.fipe = ?1
+              WHERE mr.id = ?2
+          "#.to_string(),
+
+            Sql::UpdateYearsVerified =>
+                "UPDATE model_references SET years_verified = 1 WHERE id = ?1".to_string(),
+
+            Sql::InsertBrandYear =>
+                "INSERT OR IGNORE INTO brand_years (brand_ref_id, fipe, description) VALUES (?1, ?2, ?3)".to_string(),
+
+            Sql::UpdateBrandYearLoaded => "UPDATE brand_years SET loaded = 1 WHERE id = ?1".to_string(),
+
+            // Once every year of a brand is loaded its models hold exactly the years of that reference.
+            Sql::UpdateBrandYearsVerified =>
+                r#"
+              UPDATE model_references SET years_verified = 1
+              WHERE brand_ref_id = ?1
+              AND NOT EXISTS (
+                  SELECT 1 FROM brand_years WHERE brand_ref_id = ?1 AND loaded = 0
+              )
+          "#.to_string(),
+
+            Sql::UpdatePrice =>
+                "UPDATE vehicle_references SET price_cents = ?1, price_raw = ?2 WHERE id = ?3".to_string(),
+
+            Sql::InsertInflationIndex =>
+                "INSERT OR REPLACE INTO inflation_index (series, month, value) VALUES (?1, ?2, ?3)".to_string(),
+
+            Sql::InsertFailedRequest =>
+                "INSERT INTO failed_requests (url, body, error, raw_value) VALUES (?1, ?2, ?3, ?4)".to_string(),
+
+            Sql::InsertAnomaly =>
+                "INSERT OR REPLACE INTO anomalies (vehicle_ref_id, kind, detail, ratio) VALUES (?1, ?2, ?3, ?4)".to_string(),
+
+            Sql::DeleteAnomalies => "DELETE FROM anomalies".to_string(),
+
+            Sql::InsertWatch =>
+                "INSERT OR IGNORE INTO watchlist (fipe_code, model_year, fuel_id) VALUES (?1, ?2, ?3)".to_string(),
+
+            Sql::DeleteWatch => "DELETE FROM watchlist WHERE id = ?1".to_string(),
+
+            // Re-adding a URL updates it in place, so its queued events are kept.
+            Sql::InsertWebhook =>
+                r#"
+              INSERT INTO webhooks (url, secret, events) VALUES (?1, ?2, ?3)
+              ON CONFLICT(url) DO UPDATE SET secret = excluded.secret, events = excluded.events
+          "#.to_string(),
+
+            Sql::DeleteWebhook => "DELETE FROM webhooks WHERE id = ?1".to_string(),
+
+            // Queues event ?1 with payload ?2 for every webhook subscribed to it.
+            Sql::InsertWebhookEvent =>
+                r#"
+              INSERT INTO webhook_events (webhook_id, event, payload)
+              SELECT id, ?1, ?2 FROM webhooks
+              WHERE events = '*' OR instr(',' || replace(events, ' ', '') || ',', ',' || ?1 || ',') > 0
+          "#.to_string(),
+
+            Sql::UpdateWebhookDelivered =>
+                "UPDATE webhook_events SET attempts = attempts + ?1, delivered_at = datetime('now', 'localtime') WHERE id = ?2".to_string(),
+
+            Sql::UpdateWebhookFailed =>
+                "UPDATE webhook_events SET attempts = attempts + ?1, last_error = ?2 WHERE id = ?3".to_string(),
+
+            Sql::UpdateWatchAlerted =>
+                "UPDATE watchlist SET alerted_ref_id = ?1 WHERE id = ?2".to_string(),
+
+            Sql::UpdateWatchSettings =>
+                "UPDATE config SET watch_threshold = ?1".to_string(),
+
+            Sql::InsertPriceLookup =>
+                r#"
+              INSERT OR REPLACE INTO price_lookups (
+                  fipe_code, model_year, fuel_id, ref_id, brand, model, price_cents, price_raw
+              )
+              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+          "#.to_string(),
+
+            Sql::UpdateFipeCode =>
+                r#"
+              UPDATE vehicles SET fipe_code = ?1
+              WHERE id = (SELECT vehicle_id FROM vehicle_references WHERE id = ?2)
+          "#.to_string(),
+
+            Sql::UpdateModelAttributes =>
+                r#"
+              UPDATE model_descriptors SET
+                  engine_displacement = ?1,
+                  valves = ?2,
+                  doors = ?3,
+                  transmission = ?4,
+                  turbo = ?5,
+                  body_style = ?6,
+                  fuel = ?7
+              WHERE id = ?8
+          "#.to_string(),
+
+            Sql::InsertAlias =>
+                r#"
+              INSERT OR IGNORE INTO aliases (kind, entity_id, canonical_id, label, match, score, status, ref_id)
+              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+          "#.to_string(),
+
+            // Approved aliases count as their canonical model, so a new code is not a launch.
+            Sql::RefreshModelLifecycle =>
+                r#"
+              WITH seen AS (
+                  SELECT
+                      COALESCE(md.canonical_id, md.id) AS entity_id,
+                      MIN(r.ref_date) AS first_date,
+                      MAX(r.ref_date) AS last_date
+                  FROM model_references m
+                  JOIN model_descriptors md ON m.model_id = md.id
+                  JOIN "references" r ON m.ref_id = r.id
+                  GROUP BY COALESCE(md.canonical_id, md.id)
+              )
+              INSERT INTO lifecycle (kind, entity_id, first_ref_id, last_ref_id)
+              SELECT
+                  'model',
+                  s.entity_id,
+                  (SELECT id FROM "references" WHERE ref_date = s.first_date ORDER BY id LIMIT 1),
+                  (SELECT id FROM "references" WHERE ref_date = s.last_date ORDER BY id LIMIT 1)
+              FROM seen s
+              WHERE true
+              ON CONFLICT(kind, entity_id) DO UPDATE SET
+                  first_ref_id = excluded.first_ref_id,
+                  last_ref_id = excluded.last_ref_id
+          "#.to_string(),
+
+            Sql::RefreshVehicleLifecycle =>
+                r#"
+              WITH seen AS (
+                  SELECT m.vehicle_id AS entity_id, MIN(r.ref_date) AS first_date, MAX(r.ref_date) AS last_date
+                  FROM vehicle_references m
+                  JOIN "references" r ON m.ref_id = r.id
+                  GROUP BY m.vehicle_id
+              )
+              INSERT INTO lifecycle (kind, entity_id, first_ref_id, last_ref_id)
+              SELECT
+                  'vehicle',
+                  s.entity_id,
+                  (SELECT id FROM "references" WHERE ref_date = s.first_date ORDER BY id LIMIT 1),
+                  (SELECT id FROM "references" WHERE ref_date = s.last_date ORDER BY id LIMIT 1)
+              FROM seen s
+              WHERE true
+              ON CONFLICT(kind, entity_id) DO UPDATE SET
+                  first_ref_id = excluded.first_ref_id,
+                  last_ref_id = excluded.last_ref_id
+          "#.to_string(),
+
+            Sql::UpdateAliasStatus => "UPDATE aliases SET status = ?1 WHERE id = ?2".to_string(),
+
+            Sql::UpdateCanonical { kind } =>
+                format!("UPDATE {}_descriptors SET canonical_id = ?1 WHERE id = ?2", kind),
+
+            Sql::UpdateDescriptorName { kind } =>
+                format!("UPDATE {}_descriptors SET description = ?1 WHERE id = ?2", kind),
 
             Sql::UpdateStatus =>
                 "UPDATE config SET db_status = ?1, last_update = datetime('now', 'localtime')".to_string(),
 
-            Sql::UpdateRowCount { entity } => format!("UPDATE config SET {}_rowcount = ?1", entity),
+            Sql::UpdateVehicleDescriptor =>
+                r#"
+              UPDATE vehicles
+              SET description = ?1, value = ?2, fuel_id = ?3, model_year = ?4, is_zero_km = ?5
+              WHERE id = ?6
+          "#.to_string(),
+
+            Sql::InsertReloadLog =>
+                r#"
+              INSERT INTO reload_log (kind, change, entity_id, label, ref_id)
+              VALUES (?1, ?2, ?3, ?4, ?5)
+          "#.to_string(),
+
+            // Memberships, prices and lifecycle rows below the deleted row go with it (ON DELETE CASCADE).
+            Sql::DeleteReference => "DELETE FROM \"references\" WHERE id = ?1".to_string(),
+
+            Sql::DeleteBrandReference => "DELETE FROM brand_references WHERE id = ?1".to_string(),
+
+            Sql::DeleteVehicleReference => "DELETE FROM vehicle_references WHERE id = ?1".to_string(),
+
+            Sql::DeleteStaleLifecycle =>
+                r#"
+              DELETE FROM lifecycle
+              WHERE (kind = 'model' AND NOT EXISTS (
+                  SELECT 1
+                  FROM model_references mr
+                  JOIN model_descriptors md ON mr.model_id = md.id
+                  WHERE COALESCE(md.canonical_id, md.id) = lifecycle.entity_id
+              ))
+              OR (kind = 'vehicle' AND NOT EXISTS (
+                  SELECT 1 FROM vehicle_references vr WHERE vr.vehicle_id = lifecycle.entity_id
+              ))
+          "#.to_string(),
+
+            Sql::UpdateRemoteReference =>
+                "UPDATE config SET latest_remote_reference = ?1".to_string(),
+
+            Sql::UpdateLoadEstimate { entity } =>
+                format!("UPDATE config SET {}_per_parent = ?1", entity),
+
+            Sql::SelectSchemaVersion => "PRAGMA user_version".to_string(),
+
+            Sql::SelectTableExists =>
+                "SELECT count(*) FROM sqlite_master WHERE type = 'table' AND name = ?1".to_string(),
+
+            Sql::UpdateSchemaVersion { version } => format!("PRAGMA user_version = {}", version),
+
+            // Each migration keeps the DDL as it was at its version; later migrations rebuild
+            // tables (vehicles: 3 and 4), so the Create* arms only describe the current schema.
+            Sql::Migrate { version } =>
+                match version {
+                    // zero km flag: keep FIPE's raw "32000" model year instead of the load date
+                    1 =>
+                        r#"
+                      ALTER TABLE years ADD COLUMN model_year integer;
+                      ALTER TABLE years ADD COLUMN is_zero_km integer not null default 0;
+                      UPDATE years SET model_year = CAST(substr(fipe, 1, instr(fipe, '-') - 1) AS integer);
+                      UPDATE years SET is_zero_km = 1, value = NULL WHERE model_year = 32000;
+                  "#.to_string(),
+                    // canonical vehicles, backfilled from the years already loaded
+                    2 =>
+                        r#"
+                      CREATE TABLE vehicles(
+                          id integer PRIMARY KEY,
+                          type_id integer,
+                          brand_fipe text,
+                          model_fipe text,
+                          fipe text,
+                          model_year integer,
+                          is_zero_km integer not null default 0,
+                          fuel_id integer,
+                          foreign key(type_id) references types(id),
+                          foreign key(fuel_id) references fuels(id),
+                          unique(type_id, brand_fipe, model_fipe, fipe)
+                      );
+                      CREATE TABLE vehicle_references(
+                          id integer PRIMARY KEY,
+                          vehicle_id integer,
+                          ref_id integer,
+                          year_id integer,
+                          foreign key(vehicle_id) references vehicles(id),
+                          foreign key(ref_id) references "references"(id),
+                          foreign key(year_id) references years(id),
+                          unique(vehicle_id, ref_id)
+                      );
+                      INSERT OR IGNORE INTO vehicles (type_id, brand_fipe, model_fipe, fipe, model_year, is_zero_km, fuel_id)
+                      SELECT b.type_id, b.fipe, m.fipe, y.fipe, y.model_year, y.is_zero_km, y.fuel_id
+                      FROM years y
+                      JOIN models m ON y.model_id = m.id
+                      JOIN brands b ON m.brand_id = b.id;
+                      INSERT OR IGNORE INTO vehicle_references (vehicle_id, ref_id, year_id)
+                      SELECT v.id, b.ref_id, y.id
+                      FROM years y
+                      JOIN models m ON y.model_id = m.id
+                      JOIN brands b ON m.brand_id = b.id
+                      JOIN vehicles v ON
+                          v.type_id = b.type_id
+                          AND v.brand_fipe = b.fipe
+                          AND v.model_fipe = m.fipe
+                          AND v.fipe = y.fipe;
+                      CREATE INDEX idx_vehicle_references_ref_id ON vehicle_references (ref_id);
+                      CREATE INDEX idx_vehicle_references_year_id ON vehicle_references (year_id);
+                  "#.to_string(),
+                    // normalised storage: descriptors stored once, per-reference membership rows
+                    // keep the old brand / model / year ids so existing references to them stay valid
+                    3 =>
+                        r#"
+                      CREATE TABLE brand_descriptors(
+                          id integer PRIMARY KEY,
+                          description text,
+                          fipe text,
+                          type_id integer,
+                          foreign key(type_id) references types(id),
+                          unique(fipe, type_id)
+                      );
+                      CREATE TABLE brand_references(
+                          id integer PRIMARY KEY,
+                          brand_id integer,
+                          ref_id integer,
+                          foreign key(brand_id) references brand_descriptors(id),
+                          foreign key(ref_id) references "references"(id),
+                          unique(brand_id, ref_id)
+                      );
+                      CREATE TABLE model_descriptors(
+                          id integer PRIMARY KEY,
+                          description text,
+                          fipe text,
+                          brand_id integer,
+                          foreign key(brand_id) references brand_descriptors(id),
+                          unique(fipe, brand_id)
+                      );
+                      CREATE TABLE model_references(
+                          id integer PRIMARY KEY,
+                          model_id integer,
+                          ref_id integer,
+                          foreign key(model_id) references model_descriptors(id),
+                          foreign key(ref_id) references "references"(id),
+                          unique(model_id, ref_id)
+                      );
+                      CREATE TABLE vehicles_new(
+                          id integer PRIMARY KEY,
+                          description text,
+                          value date,
+                          fipe text,
+                          model_id integer,
+                          fuel_id integer,
+                          model_year integer,
+                          is_zero_km integer not null default 0,
+                          foreign key(model_id) references model_descriptors(id),
+                          foreign key(fuel_id) references fuels(id),
+                          unique(fipe, model_id)
+                      );
+                      CREATE TABLE vehicle_references_new(
+                          id integer PRIMARY KEY,
+                          vehicle_id integer,
+                          ref_id integer,
+                          foreign key(vehicle_id) references vehicles_new(id),
+                          foreign key(ref_id) references "references"(id),
+                          unique(vehicle_id, ref_id)
+                      );
+
+                      INSERT INTO brand_descriptors (description, fipe, type_id)
+                      SELECT description, fipe, type_id
+                      FROM (SELECT description, fipe, type_id, MIN(id) FROM brands GROUP BY fipe, type_id);
+                      INSERT OR IGNORE INTO brand_references (id, brand_id, ref_id)
+                      SELECT b.id, bd.id, b.ref_id
+                      FROM brands b
+                      JOIN brand_descriptors bd ON bd.fipe = b.fipe AND bd.type_id = b.type_id;
+
+                      INSERT INTO model_descriptors (description, fipe, brand_id)
+                      SELECT description, fipe, brand_id
+                      FROM (
+                          SELECT m.description, m.fipe, br.brand_id, MIN(m.id)
+                          FROM models m
+                          JOIN brand_references br ON m.brand_id = br.id
+                          GROUP BY m.fipe, br.brand_id
+                      );
+                      INSERT OR IGNORE INTO model_references (id, model_id, ref_id)
+                      SELECT m.id, md.id, br.ref_id
+                      FROM models m
+                      JOIN brand_references br ON m.brand_id = br.id
+                      JOIN model_descriptors md ON md.fipe = m.fipe AND md.brand_id = br.brand_id;
+
+                      INSERT INTO vehicles_new (id, description, value, fipe, model_id, fuel_id, model_year, is_zero_km)
+                      SELECT v.id, y.description, y.value, v.fipe, md.id, v.fuel_id, v.model_year, v.is_zero_km
+                      FROM vehicles v
+                      JOIN brand_descriptors bd ON bd.fipe = v.brand_fipe AND bd.type_id = v.type_id
+                      JOIN model_descriptors md ON md.fipe = v.model_fipe AND md.brand_id = bd.id
+                      JOIN years y ON y.id = (
+                          SELECT MIN(vr.year_id) FROM vehicle_references vr WHERE vr.vehicle_id = v.id
+                      );
+                      INSERT OR IGNORE INTO vehicle_references_new (id, vehicle_id, ref_id)
+                      SELECT year_id, vehicle_id, ref_id FROM vehicle_references;
+
+                      DROP TABLE vehicle_references;
+                      DROP TABLE vehicles;
+                      DROP TABLE years;
+                      DROP TABLE models;
+                      DROP TABLE brands;
+                      ALTER TABLE vehicles_new RENAME TO vehicles;
+                      ALTER TABLE vehicle_references_new RENAME TO vehicle_references;
+
+                      CREATE VIEW brands AS
+                      SELECT
+                          br.id AS id,
+                          bd.description AS description,
+                          bd.fipe AS fipe,
+                          bd.type_id AS type_id,
+                          br.ref_id AS ref_id
+                      FROM brand_references br
+                      JOIN brand_descriptors bd ON br.brand_id = bd.id;
+
+                      CREATE VIEW models AS
+                      SELECT
+                          mr.id AS id,
+                          md.description AS description,
+                          md.fipe AS fipe,
+                          br.id AS brand_id
+                      FROM model_references mr
+                      JOIN model_descriptors md ON mr.model_id = md.id
+                      JOIN brand_references br ON br.brand_id = md.brand_id AND br.ref_id = mr.ref_id;
+
+                      CREATE VIEW years AS
+                      SELECT
+                          vr.id AS id,
+                          v.description AS description,
+                          v.value AS value,
+                          v.fipe AS fipe,
+                          mr.id AS model_id,
+                          v.fuel_id AS fuel_id,
+                          v.model_year AS model_year,
+                          v.is_zero_km AS is_zero_km
+                      FROM vehicle_references vr
+                      JOIN vehicles v ON vr.vehicle_id = v.id
+                      JOIN model_references mr ON mr.model_id = v.model_id AND mr.ref_id = vr.ref_id;
+
+                      CREATE INDEX IF NOT EXISTS idx_references_id ON "references" (id);
+                      CREATE INDEX IF NOT EXISTS idx_types_id ON types (id);
+                      CREATE INDEX IF NOT EXISTS idx_fuels_id ON fuels (id);
+                      CREATE INDEX IF NOT EXISTS idx_brand_descriptors_type_id ON brand_descriptors (type_id);
+                      CREATE INDEX IF NOT EXISTS idx_brand_references_ref_id ON brand_references (ref_id);
+                      CREATE INDEX IF NOT EXISTS idx_model_descriptors_brand_id ON model_descriptors (brand_id);
+                      CREATE INDEX IF NOT EXISTS idx_model_references_ref_id ON model_references (ref_id);
+                      CREATE INDEX IF NOT EXISTS idx_vehicles_model_id ON vehicles (model_id);
+                      CREATE INDEX IF NOT EXISTS idx_vehicles_fuel_id ON vehicles (fuel_id);
+                      CREATE INDEX IF NOT EXISTS idx_vehicle_references_ref_id ON vehicle_references (ref_id);
+                  "#.to_string(),
+                    // prices per reference and FIPE's own vehicle code
+                    4 =>
+                        r#"
+                      ALTER TABLE vehicles ADD COLUMN fipe_code text;
+                      ALTER TABLE vehicle_references ADD COLUMN price real;
+                      CREATE INDEX idx_vehicles_fipe_code ON vehicles (fipe_code);
+                  "#.to_string(),
+                    // full-text search over brand and model descriptors
+                    5 =>
+                        r#"
+                      CREATE VIRTUAL TABLE search_index USING fts5(
+                          brand,
+                          model,
+                          kind UNINDEXED,
+                          entity_id UNINDEXED,
+                          tokenize = 'unicode61 remove_diacritics 2'
+                      );
+
+                      CREATE TRIGGER brand_descriptors_search_insert AFTER INSERT ON brand_descriptors
+                      BEGIN
+                          INSERT INTO search_index (brand, model, kind, entity_id)
+                          VALUES (new.description, '', 'brand', new.id);
+                      END;
+
+                      CREATE TRIGGER brand_descriptors_search_update AFTER UPDATE OF description ON brand_descriptors
+                      BEGIN
+                          UPDATE search_index SET brand = new.description
+                          WHERE (kind = 'brand' AND entity_id = new.id)
+                          OR (kind = 'model' AND entity_id IN (SELECT id FROM model_descriptors WHERE brand_id = new.id));
+                      END;
+
+                      CREATE TRIGGER brand_descriptors_search_delete AFTER DELETE ON brand_descriptors
+                      BEGIN
+                          DELETE FROM search_index WHERE kind = 'brand' AND entity_id = old.id;
+                      END;
+
+                      CREATE TRIGGER model_descriptors_search_insert AFTER INSERT ON model_descriptors
+                      BEGIN
+                          INSERT INTO search_index (brand, model, kind, entity_id)
+                          SELECT bd.description, new.description, 'model', new.id
+                          FROM brand_descriptors bd
+                          WHERE bd.id = new.brand_id;
+                      END;
+
+                      CREATE TRIGGER model_descriptors_search_update AFTER UPDATE OF description ON model_descriptors
+                      BEGIN
+                          UPDATE search_index SET model = new.description WHERE kind = 'model' AND entity_id = new.id;
+                      END;
+
+                      CREATE TRIGGER model_descriptors_search_delete AFTER DELETE ON model_descriptors
+                      BEGIN
+                          DELETE FROM search_index WHERE kind = 'model' AND entity_id = old.id;
+                      END;
+
+                      INSERT INTO search_index (brand, model, kind, entity_id)
+                      SELECT description, '', 'brand', id FROM brand_descriptors;
+                      INSERT INTO search_index (brand, model, kind, entity_id)
+                      SELECT bd.description, md.description, 'model', md.id
+                      FROM model_descriptors md
+                      JOIN brand_descriptors bd ON md.brand_id = bd.id;
+                  "#.to_string(),
+                    // attributes parsed from model labels; filled by the backfill maintenance action
+                    6 =>
+                        r#"
+                      ALTER TABLE model_descriptors ADD COLUMN engine_displacement real;
+                      ALTER TABLE model_descriptors ADD COLUMN valves integer;
+                      ALTER TABLE model_descriptors ADD COLUMN doors integer;
+                      ALTER TABLE model_descriptors ADD COLUMN transmission text;
+                      ALTER TABLE model_descriptors ADD COLUMN turbo integer not null default 0;
+                      ALTER TABLE model_descriptors ADD COLUMN body_style text;
+                      ALTER TABLE model_descriptors ADD COLUMN fuel text;
+                  "#.to_string(),
+                    // brand / model name aliases
+                    7 =>
+                        r#"
+                      ALTER TABLE brand_descriptors ADD COLUMN canonical_id integer references brand_descriptors(id);
+                      ALTER TABLE model_descriptors ADD COLUMN canonical_id integer references model_descriptors(id);
+                      CREATE TABLE aliases(
+                          id integer PRIMARY KEY,
+                          kind text,
+                          entity_id integer,
+                          canonical_id integer,
+                          label text,
+                          match text,
+                          score real,
+                          status text default 'pending',
+                          ref_id integer,
+                          detected_at date default (datetime('now', 'localtime')),
+                          foreign key(ref_id) references "references"(id),
+                          unique(kind, entity_id, canonical_id, label)
+                      );
+                  "#.to_string(),
+                    // model / model-year lifecycle
+                    8 =>
+                        r#"
+                      CREATE TABLE lifecycle(
+                          id integer PRIMARY KEY,
+                          kind text,
+                          entity_id integer,
+                          first_ref_id integer,
+                          last_ref_id integer,
+                          foreign key(first_ref_id) references "references"(id),
+                          foreign key(last_ref_id) references "references"(id),
+                          unique(kind, entity_id)
+                      );
+                      WITH seen AS (
+                          SELECT m.model_id AS entity_id, MIN(r.ref_date) AS first_date, MAX(r.ref_date) AS last_date
+                          FROM model_references m
+                          JOIN "references" r ON m.ref_id = r.id
+                          GROUP BY m.model_id
+                      )
+                      INSERT INTO lifecycle (kind, entity_id, first_ref_id, last_ref_id)
+                      SELECT
+                          'model',
+                          s.entity_id,
+                          (SELECT id FROM "references" WHERE ref_date = s.first_date ORDER BY id LIMIT 1),
+                          (SELECT id FROM "references" WHERE ref_date = s.last_date ORDER BY id LIMIT 1)
+                      FROM seen s
+                      WHERE true
+                      ON CONFLICT(kind, entity_id) DO UPDATE SET
+                          first_ref_id = excluded.first_ref_id,
+                          last_ref_id = excluded.last_ref_id;
+                      WITH seen AS (
+                          SELECT m.vehicle_id AS entity_id, MIN(r.ref_date) AS first_date, MAX(r.ref_date) AS last_date
+                          FROM vehicle_references m
+                          JOIN "references" r ON m.ref_id = r.id
+                          GROUP BY m.vehicle_id
+                      )
+                      INSERT INTO lifecycle (kind, entity_id, first_ref_id, last_ref_id)
+                      SELECT
+                          'vehicle',
+                          s.entity_id,
+                          (SELECT id FROM "references" WHERE ref_date = s.first_date ORDER BY id LIMIT 1),
+                          (SELECT id FROM "references" WHERE ref_date = s.last_date ORDER BY id LIMIT 1)
+                      FROM seen s
+                      WHERE true
+                      ON CONFLICT(kind, entity_id) DO UPDATE SET
+                          first_ref_id = excluded.first_ref_id,
+                          last_ref_id = excluded.last_ref_id;
+                  "#.to_string(),
+                    // newest reference published by FIPE
+                    9 =>
+                        r#"
+                      ALTER TABLE config ADD COLUMN latest_remote_reference text;
+                  "#.to_string(),
+                    // rows-per-parent load estimates replace the fixed row counts
+                    10 =>
+                        r#"
+                      ALTER TABLE config DROP COLUMN brands_rowcount;
+                      ALTER TABLE config DROP COLUMN models_rowcount;
+                      ALTER TABLE config DROP COLUMN years_rowcount;
+                      ALTER TABLE config DROP COLUMN vehicles_rowcount;
+                      ALTER TABLE config ADD COLUMN brands_per_parent real default 60;
+                      ALTER TABLE config ADD COLUMN models_per_parent real default 40;
+                      ALTER TABLE config ADD COLUMN years_per_parent real default 4;
+                  "#.to_string(),
+                    // parent membership ids, cascading deletes; tables are rebuilt to change their keys
+                    11 =>
+                        r#"
+                      DROP VIEW IF EXISTS years;
+                      DROP VIEW IF EXISTS models;
+                      DROP VIEW IF EXISTS brands;
+
+                      CREATE TABLE brand_references_new(
+                          id integer PRIMARY KEY,
+                          brand_id integer,
+                          ref_id integer,
+                          foreign key(brand_id) references brand_descriptors(id) ON DELETE CASCADE,
+                          foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                          unique(brand_id, ref_id)
+                      );
+                      INSERT INTO brand_references_new (id, brand_id, ref_id)
+                      SELECT id, brand_id, ref_id FROM brand_references;
+                      DROP TABLE brand_references;
+                      ALTER TABLE brand_references_new RENAME TO brand_references;
+
+                      CREATE TABLE model_references_new(
+                          id integer PRIMARY KEY,
+                          model_id integer,
+                          ref_id integer,
+                          brand_ref_id integer,
+                          foreign key(model_id) references model_descriptors(id) ON DELETE CASCADE,
+                          foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                          foreign key(brand_ref_id) references brand_references(id) ON DELETE CASCADE,
+                          unique(model_id, ref_id)
+                      );
+                      INSERT INTO model_references_new (id, model_id, ref_id, brand_ref_id)
+                      SELECT
+                          mr.id,
+                          mr.model_id,
+                          mr.ref_id,
+                          (
+                              SELECT br.id
+                              FROM brand_references br
+                              JOIN model_descriptors md ON md.brand_id = br.brand_id
+                              WHERE md.id = mr.model_id
+                              AND br.ref_id = mr.ref_id
+                          )
+                      FROM model_references mr;
+                      DROP TABLE model_references;
+                      ALTER TABLE model_references_new RENAME TO model_references;
+
+                      CREATE TABLE vehicle_references_new(
+                          id integer PRIMARY KEY,
+                          vehicle_id integer,
+                          ref_id integer,
+                          model_ref_id integer,
+                          price real,
+                          foreign key(vehicle_id) references vehicles(id) ON DELETE CASCADE,
+                          foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                          foreign key(model_ref_id) references model_references(id) ON DELETE CASCADE,
+                          unique(vehicle_id, ref_id)
+                      );
+                      INSERT INTO vehicle_references_new (id, vehicle_id, ref_id, model_ref_id, price)
+                      SELECT
+                          vr.id,
+                          vr.vehicle_id,
+                          vr.ref_id,
+                          (
+                              SELECT mr.id
+                              FROM model_references mr
+                              JOIN vehicles v ON v.model_id = mr.model_id
+                              WHERE v.id = vr.vehicle_id
+                              AND mr.ref_id = vr.ref_id
+                          ),
+                          vr.price
+                      FROM vehicle_references vr;
+                      DROP TABLE vehicle_references;
+                      ALTER TABLE vehicle_references_new RENAME TO vehicle_references;
+
+                      CREATE TABLE lifecycle_new(
+                          id integer PRIMARY KEY,
+                          kind text,
+                          entity_id integer,
+                          first_ref_id integer,
+                          last_ref_id integer,
+                          foreign key(first_ref_id) references "references"(id) ON DELETE CASCADE,
+                          foreign key(last_ref_id) references "references"(id) ON DELETE CASCADE,
+                          unique(kind, entity_id)
+                      );
+                      INSERT INTO lifecycle_new SELECT * FROM lifecycle;
+                      DROP TABLE lifecycle;
+                      ALTER TABLE lifecycle_new RENAME TO lifecycle;
+
+                      CREATE TABLE aliases_new(
+                          id integer PRIMARY KEY,
+                          kind text,
+                          entity_id integer,
+                          canonical_id integer,
+                          label text,
+                          match text,
+                          score real,
+                          status text default 'pending',
+                          ref_id integer,
+                          detected_at date default (datetime('now', 'localtime')),
+                          foreign key(ref_id) references "references"(id) ON DELETE SET NULL,
+                          unique(kind, entity_id, canonical_id, label)
+                      );
+                      INSERT INTO aliases_new SELECT * FROM aliases;
+                      DROP TABLE aliases;
+                      ALTER TABLE aliases_new RENAME TO aliases;
+
+                      CREATE INDEX IF NOT EXISTS idx_brand_references_ref_id ON brand_references (ref_id);
+                      CREATE INDEX IF NOT EXISTS idx_model_references_ref_id ON model_references (ref_id);
+                      CREATE INDEX IF NOT EXISTS idx_vehicle_references_ref_id ON vehicle_references (ref_id);
+                      CREATE INDEX IF NOT EXISTS idx_model_references_brand_ref_id ON model_references (brand_ref_id);
+                      CREATE INDEX IF NOT EXISTS idx_vehicle_references_model_ref_id ON vehicle_references (model_ref_id);
+
+                      CREATE VIEW brands AS
+                      SELECT
+                          br.id AS id,
+                          bd.description AS description,
+                          bd.fipe AS fipe,
+                          bd.type_id AS type_id,
+                          br.ref_id AS ref_id
+                      FROM brand_references br
+                      JOIN brand_descriptors bd ON br.brand_id = bd.id;
+
+                      CREATE VIEW models AS
+                      SELECT
+                          mr.id AS id,
+                          md.description AS description,
+                          md.fipe AS fipe,
+                          br.id AS brand_id
+                      FROM model_references mr
+                      JOIN model_descriptors md ON mr.model_id = md.id
+                      JOIN brand_references br ON br.brand_id = md.brand_id AND br.ref_id = mr.ref_id;
+
+                      CREATE VIEW years AS
+                      SELECT
+                          vr.id AS id,
+                          v.description AS description,
+                          v.value AS value,
+                          v.fipe AS fipe,
+                          mr.id AS model_id,
+                          v.fuel_id AS fuel_id,
+                          v.model_year AS model_year,
+                          v.is_zero_km AS is_zero_km
+                      FROM vehicle_references vr
+                      JOIN vehicles v ON vr.vehicle_id = v.id
+                      JOIN model_references mr ON mr.model_id = v.model_id AND mr.ref_id = vr.ref_id;
+                  "#.to_string(),
+                    // targeted reload log
+                    12 =>
+                        r#"
+                      CREATE TABLE reload_log(
+                          id integer PRIMARY KEY,
+                          kind text,
+                          change text,
+                          entity_id integer,
+                          label text,
+                          ref_id integer,
+                          detected_at date default (datetime('now', 'localtime')),
+                          foreign key(ref_id) references "references"(id) ON DELETE CASCADE
+                      );
+                  "#.to_string(),
+                    // model references whose years were fetched from their own reference
+                    13 =>
+                        r#"
+                      ALTER TABLE model_references ADD COLUMN years_verified integer not null default 0;
+                  "#.to_string(),
+                    // brand-level year lists
+                    14 =>
+                        r#"
+                      CREATE TABLE brand_years(
+                          id integer PRIMARY KEY,
+                          brand_ref_id integer,
+                          fipe text,
+                          description text,
+                          loaded integer not null default 0,
+                          foreign key(brand_ref_id) references brand_references(id) ON DELETE CASCADE,
+                          unique(brand_ref_id, fipe)
+                      );
+                  "#.to_string(),
+                    // prices looked up by FIPE code
+                    15 =>
+                        r#"
+                      CREATE TABLE price_lookups(
+                          id integer PRIMARY KEY,
+                          fipe_code text,
+                          model_year integer,
+                          fuel_id integer,
+                          ref_id integer,
+                          brand text,
+                          model text,
+                          price real,
+                          fetched_at date default (datetime('now', 'localtime')),
+                          foreign key(fuel_id) references fuels(id),
+                          foreign key(ref_id) references "references"(id) ON DELETE CASCADE,
+                          unique(fipe_code, model_year, fuel_id, ref_id)
+                      );
+                  "#.to_string(),
+                    // imported inflation series
+                    16 =>
+                        r#"
+                      CREATE TABLE inflation_index(
+                          id integer PRIMARY KEY,
+                          series text,
+                          month date,
+                          value real,
+                          unique(series, month)
+                      );
+                  "#.to_string(),
+                    // prices in integer cents with FIPE's original string; failed requests log
+                    17 =>
+                        r#"
+                      ALTER TABLE vehicle_references ADD COLUMN price_cents integer;
+                      ALTER TABLE vehicle_references ADD COLUMN price_raw text;
+                      UPDATE vehicle_references SET price_cents = CAST(round(price * 100) AS integer)
+                      WHERE price IS NOT NULL;
+                      ALTER TABLE vehicle_references DROP COLUMN price;
+                      ALTER TABLE price_lookups ADD COLUMN price_cents integer;
+                      ALTER TABLE price_lookups ADD COLUMN price_raw text;
+                      UPDATE price_lookups SET price_cents = CAST(round(price * 100) AS integer)
+                      WHERE price IS NOT NULL;
+                      ALTER TABLE price_lookups DROP COLUMN price;
+                      CREATE TABLE failed_requests(
+                          id integer PRIMARY KEY,
+                          url text,
+                          body text,
+                          error text,
+                          raw_value text,
+                          failed_at date default (datetime('now', 'localtime'))
+                      );
+                  "#.to_string(),
+                    // price anomalies
+                    18 =>
+                        r#"
+                      CREATE TABLE anomalies(
+                          id integer PRIMARY KEY,
+                          vehicle_ref_id integer,
+                          kind text,
+                          detail text,
+                          ratio real,
+                          detected_at date default (datetime('now', 'localtime')),
+                          foreign key(vehicle_ref_id) references vehicle_references(id) ON DELETE CASCADE,
+                          unique(vehicle_ref_id, kind)
+                      );
+                  "#.to_string(),
+                    // watchlist and its alert settings
+                    19 =>
+                        r#"
+                      CREATE TABLE watchlist(
+                          id integer PRIMARY KEY,
+                          fipe_code text,
+                          model_year integer,
+                          fuel_id integer,
+                          alerted_ref_id integer,
+                          added_at date default (datetime('now', 'localtime')),
+                          foreign key(fuel_id) references fuels(id),
+                          unique(fipe_code, model_year, fuel_id)
+                      );
+                      ALTER TABLE config ADD COLUMN watch_threshold real default 5;
+                      ALTER TABLE config ADD COLUMN watch_webhook text;
+                  "#.to_string(),
+                    // outbound webhooks
+                    20 =>
+                        r#"
+                      CREATE TABLE webhooks(
+                          id integer PRIMARY KEY,
+                          url text unique,
+                          secret text,
+                          events text not null default '*',
+                          created_at date default (datetime('now', 'localtime'))
+                      );
+
+                      CREATE TABLE webhook_events(
+                          id integer PRIMARY KEY,
+                          webhook_id integer,
+                          event text,
+                          payload text,
+                          attempts integer not null default 0,
+                          last_error text,
+                          created_at date default (datetime('now', 'localtime')),
+                          delivered_at date,
+                          foreign key(webhook_id) references webhooks(id) ON DELETE CASCADE
+                      );
+                  "#.to_string(),
+                    // watchlist alerts go out as a webhook event instead of their own URL,
+                    // which becomes a webhook subscribed to them
+                    21 =>
+                        r#"
+                      INSERT INTO webhooks (url, events)
+                      SELECT trim(watch_webhook), 'watchlist.alert' FROM config WHERE trim(watch_webhook) <> ''
+                      ON CONFLICT(url) DO UPDATE SET events = events || ',watchlist.alert'
+                      WHERE events <> '*' AND instr(events, 'watchlist.alert') = 0;
+                      ALTER TABLE config DROP COLUMN watch_webhook;
+                  "#.to_string(),
+                    _ => String::new(),
+                },
         }
     }
 }
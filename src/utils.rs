@@ -1,11 +1,14 @@
 use crate::label::{ Label };
-use crate::schema::{ References };
-use chrono::{ Datelike, NaiveDate, Utc };
+use crate::schema::{ ParsedYear, References };
+use chrono::{ Datelike, NaiveDate };
 use indicatif::{ ProgressBar, ProgressStyle };
+use owo_colors::OwoColorize;
 use rand::{ Rng };
 use std::io::{ Write };
 use rand::seq::{ IndexedRandom };
 
+pub const ZERO_KM_YEAR: i32 = 32000;
+
 pub fn clear_screen() {
     print!("\x1B[2J\x1B[1;1H");
 }
@@ -61,6 +64,26 @@ pub fn progress_bar(len: u64) -> ProgressBar {
     pb
 }
 
+pub fn estimate_rows(per_parent: f64, parents: usize) -> u64 {
+    (per_parent * parents as f64).ceil() as u64
+}
+
+// Re-sizes a load's progress bar once some parents are done, using the rows per parent
+// seen so far in this run, so the bar length and ETA converge on the real total.
+pub fn rescale_progress(
+    pb: &ProgressBar,
+    parents_done: usize,
+    parents_left: usize,
+    per_parent: f64
+) {
+    let per_parent = if parents_done > 0 && pb.position() > 0 {
+        (pb.position() as f64) / (parents_done as f64)
+    } else {
+        per_parent
+    };
+    pb.set_length(pb.position() + estimate_rows(per_parent, parents_left));
+}
+
 pub fn parse_ref_date(reference: &References) -> String {
     let date = NaiveDate::parse_from_str(&reference.ref_date, "%Y-%m-%d").unwrap_or_else(|_|
         NaiveDate::from_ymd_opt(1900, 1, 1).unwrap()
@@ -95,16 +118,171 @@ pub fn get_random_user_agent() -> &'static str {
 
     AGENTS.choose(&mut rand::rng()).unwrap_or(&AGENTS[0])
 }
-pub fn parse_year(y: &str) -> (String, Option<String>) {
+// FIPE encodes zero km vehicles with the model year "32000"; it is kept as-is and flagged.
+// None when the code doesn't start with a year.
+pub fn parse_year(y: &str) -> Option<ParsedYear> {
     let mut parts = y.splitn(2, '-');
     let year_raw = parts.next().unwrap_or("").trim();
     let fuel_id = parts.next().map(|s| s.to_string());
-    let year_str = if year_raw == "32000" {
-        Utc::now().year().to_string()
-    } else {
-        year_raw.to_string()
-    };
-    let year_date = format!("{}-01-01", year_str);
+    let model_year = year_raw.parse::<i32>().ok()?;
+    let is_zero_km = model_year == ZERO_KM_YEAR;
+    let value = if is_zero_km { None } else { Some(format!("{}-01-01", model_year)) };
+
+    Some(ParsedYear { value, model_year, is_zero_km, fuel_id })
+}
+
+// "R$ 45.123,00" -> 4512300. Anything else (no "R$", bad thousands grouping, not exactly
+// two decimals) is rejected rather than guessed at.
+pub fn parse_price_cents(valor: &str) -> Option<i64> {
+    let number = valor.trim().strip_prefix("R$")?.trim_start();
+    let (units, cents) = number.split_once(',')?;
+    let groups: Vec<&str> = units.split('.').collect();
+    let is_digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if cents.len() != 2
+        || !is_digits(cents)
+        || groups[0].len() > 3
+        || groups[1..].iter().any(|g| g.len() != 3)
+        || !groups.iter().all(|g| is_digits(g))
+    {
+        return None;
+    }
+    let units: i64 = groups.concat().parse().ok()?;
+    units.checked_mul(100)?.checked_add(cents.parse().ok()?)
+}
+
+pub fn format_price(price: f64) -> String {
+    let cents = (price * 100.0).round() as i64;
+    let units = (cents.abs() / 100).to_string();
+    let mut grouped = String::new();
+    for (i, c) in units.chars().enumerate() {
+        if i > 0 && (units.len() - i).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(c);
+    }
+    let sign = if cents < 0 { "-" } else { "" };
+    format!("{}R$ {},{:02}", sign, grouped, cents.abs() % 100)
+}
+
+pub fn pct_change(from: Option<f64>, to: f64) -> Option<f64> {
+    match from {
+        Some(f) if f != 0.0 => Some((to / f - 1.0) * 100.0),
+        _ => None,
+    }
+}
+
+pub fn format_pct(pct: Option<f64>) -> String {
+    match pct {
+        Some(p) => format!("{:+.2}%", p),
+        None => "-".to_string(),
+    }
+}
+
+pub fn format_model_year(model_year: i32) -> String {
+    if model_year == ZERO_KM_YEAR { "0 km".to_string() } else { model_year.to_string() }
+}
 
-    (year_date, fuel_id)
+pub fn vehicle_type_slug(type_id: i32) -> &'static str {
+    match type_id {
+        2 => "moto",
+        3 => "caminhao",
+        _ => "carro",
+    }
+}
+
+pub fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i < widths.len() {
+                widths[i] = widths[i].max(cell.chars().count());
+            }
+        }
+    }
+
+    let header = headers
+        .iter()
+        .enumerate()
+        .map(|(i, h)| format!("{:<width$}", h, width = widths[i]))
+        .collect::<Vec<String>>()
+        .join(" | ");
+    println!("{}", header.bold());
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|w| "-".repeat(*w))
+            .collect::<Vec<String>>()
+            .join("-+-")
+            .dimmed()
+    );
+    for row in rows {
+        let line = row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| format!("{:<width$}", cell, width = widths.get(i).copied().unwrap_or(0)))
+            .collect::<Vec<String>>()
+            .join(" | ");
+        println!("{}", line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_year_keeps_zero_km_code() {
+        let year = parse_year("32000-1").unwrap();
+        assert_eq!(year.model_year, ZERO_KM_YEAR);
+        assert!(year.is_zero_km);
+        assert_eq!(year.value, None);
+        assert_eq!(year.fuel_id.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn parse_year_dates_model_year() {
+        let year = parse_year("2015-3").unwrap();
+        assert_eq!(year.model_year, 2015);
+        assert!(!year.is_zero_km);
+        assert_eq!(year.value.as_deref(), Some("2015-01-01"));
+        assert_eq!(year.fuel_id.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn parse_year_rejects_bad_code() {
+        assert!(parse_year("").is_none());
+        assert!(parse_year("zero-1").is_none());
+    }
+
+    #[test]
+    fn parse_price_cents_reads_brazilian_format() {
+        assert_eq!(parse_price_cents("R$ 1.234,56"), Some(123456));
+        assert_eq!(parse_price_cents("R$ 45.123,00"), Some(4512300));
+        assert_eq!(parse_price_cents("R$ 1.234.567,89"), Some(123456789));
+        assert_eq!(parse_price_cents("R$ 999,99"), Some(99999));
+    }
+
+    #[test]
+    fn parse_price_cents_requires_currency() {
+        assert_eq!(parse_price_cents("1.234,56"), None);
+    }
+
+    #[test]
+    fn parse_price_cents_rejects_bad_grouping() {
+        assert_eq!(parse_price_cents("R$ 1.23,00"), None);
+        assert_eq!(parse_price_cents("R$ 1234.567,00"), None);
+    }
+
+    #[test]
+    fn parse_price_cents_requires_two_decimals() {
+        assert_eq!(parse_price_cents("R$ 1.234,5"), None);
+        assert_eq!(parse_price_cents("R$ 1.234,567"), None);
+    }
+
+    #[test]
+    fn parse_price_cents_rejects_overflow() {
+        assert_eq!(parse_price_cents("R$ 92.233.720.368.547.758,08"), None);
+        assert_eq!(parse_price_cents("R$ 999.999.999.999.999.999.999,00"), None);
+    }
 }
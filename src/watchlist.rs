@@ -0,0 +1,224 @@
+use crate::export::write_json;
+use crate::label::Label;
+use crate::menu::WatchMenu;
+use crate::prompts::{prompt_choice, prompt_fuel, render_config};
+use crate::schema::{Choice, WatchAlerts, WatchItem, WatchSettings};
+use crate::sql::Sql;
+use crate::utils::{format_model_year, format_pct, format_price, pct_change, print_table};
+use crate::webhooks::{deliver_webhooks, Event};
+use inquire::{CustomType, Select, Text};
+use rusqlite::{params, Connection};
+
+const WATCH_HEADERS: [&str; 9] = [
+    "Brand",
+    "Model",
+    "FIPE Code",
+    "Year",
+    "Fuel",
+    "Reference",
+    "Price",
+    "Previous",
+    "Change",
+];
+
+pub fn select_watchlist(conn: &Connection) -> Result<Vec<WatchItem>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectWatchlist.get().as_str())?;
+    let item_iter = stmt.query_map([], |row| {
+        let old_price: Option<f64> = row.get("old_price")?;
+        let new_price: Option<f64> = row.get("new_price")?;
+        Ok(WatchItem {
+            id: row.get("id")?,
+            fipe_code: row.get("fipe_code")?,
+            model_year: row.get("model_year")?,
+            fuel: row.get("fuel_description")?,
+            brand: row.get("brand_description")?,
+            model: row.get("model_description")?,
+            alerted_ref_id: row.get("alerted_ref_id")?,
+            ref_id: row.get("ref_id")?,
+            reference: row.get("ref_date")?,
+            new_price,
+            old_reference: row.get("old_ref_date")?,
+            old_price,
+            pct_change: new_price.and_then(|p| pct_change(old_price, p)),
+        })
+    })?;
+
+    let mut items = Vec::new();
+    for item in item_iter {
+        items.push(item?);
+    }
+    Ok(items)
+}
+
+pub fn select_watch_settings(
+    conn: &Connection,
+) -> Result<WatchSettings, Box<dyn std::error::Error>> {
+    Ok(
+        conn.query_row(Sql::SelectWatchSettings.get().as_str(), [], |row| {
+            Ok(WatchSettings {
+                threshold: row.get("watch_threshold")?,
+            })
+        })?,
+    )
+}
+
+fn watch_row(item: &WatchItem) -> Vec<String> {
+    vec![
+        item.brand.clone().unwrap_or_default(),
+        item.model.clone().unwrap_or_default(),
+        item.fipe_code.clone(),
+        format_model_year(item.model_year),
+        item.fuel.clone(),
+        item.reference.clone().unwrap_or_else(|| "-".to_string()),
+        item.new_price
+            .map(format_price)
+            .unwrap_or_else(|| "-".to_string()),
+        item.old_price
+            .map(format_price)
+            .unwrap_or_else(|| "-".to_string()),
+        format_pct(item.pct_change),
+    ]
+}
+
+// Watched vehicles whose latest price moved at least the configured threshold since the
+// previous reference. Each reference is only alerted once; run after every price load.
+pub fn watchlist_alerts(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = select_watch_settings(conn)?;
+    let items = select_watchlist(conn)?;
+    let pending: Vec<&WatchItem> = items
+        .iter()
+        .filter(|i| i.ref_id.is_some() && i.ref_id != i.alerted_ref_id)
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let alerts: Vec<&WatchItem> = pending
+        .iter()
+        .copied()
+        .filter(|i| i.pct_change.is_some_and(|c| c.abs() >= settings.threshold))
+        .collect();
+    // The alert is queued and the items marked together, so nothing is marked unless it was queued.
+    let tx = conn.unchecked_transaction()?;
+    if !alerts.is_empty() {
+        (Label::WatchAlertTitle {
+            count: &alerts.len().to_string(),
+            threshold: &settings.threshold.to_string(),
+        })
+        .log();
+        let rows: Vec<Vec<String>> = alerts.iter().map(|i| watch_row(i)).collect();
+        print_table(&WATCH_HEADERS, &rows);
+
+        let payload = WatchAlerts {
+            threshold: settings.threshold,
+            alerts,
+        };
+        write_json("watchlist_alerts", &payload)?;
+        (Event::WatchlistAlert { alerts: &payload }).emit(&tx)?;
+    }
+
+    {
+        let mut stmt = tx.prepare(Sql::UpdateWatchAlerted.get().as_str())?;
+        for item in pending {
+            stmt.execute(params![item.ref_id, item.id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn add_watch(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let code = Text::new("FIPE code (e.g. 005340-6):").prompt()?;
+    let year = CustomType::<i32>::new("Model year (32000 for zero km):").prompt()?;
+    let Some(fuel) = prompt_fuel(conn)? else {
+        return Ok(());
+    };
+    let code = code.trim();
+    conn.execute(
+        Sql::InsertWatch.get().as_str(),
+        params![code, year, fuel.id],
+    )?;
+    (Label::WatchAdded {
+        vehicle: &format!(
+            "{} | {} | {}",
+            code,
+            format_model_year(year),
+            fuel.description
+        ),
+    })
+    .log();
+    Ok(())
+}
+
+fn remove_watch(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let choices = select_watchlist(conn)?
+        .into_iter()
+        .map(|i| Choice {
+            id: i.id,
+            description: format!(
+                "{} | {} | {} {}",
+                i.fipe_code,
+                format_model_year(i.model_year),
+                i.fuel,
+                i.model.map(|m| format!("({})", m)).unwrap_or_default()
+            ),
+        })
+        .collect();
+    let Some(item) = prompt_choice("Remove", choices)? else {
+        Label::WatchlistEmpty.log();
+        return Ok(());
+    };
+    conn.execute(Sql::DeleteWatch.get().as_str(), [item.id])?;
+    (Label::DeleteOk {
+        what: &item.description,
+    })
+    .log();
+    Ok(())
+}
+
+fn edit_watch_settings(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = select_watch_settings(conn)?;
+    let threshold = CustomType::<f64>::new("Alert when the price moves at least (%):")
+        .with_default(settings.threshold)
+        .prompt()?;
+    conn.execute(
+        Sql::UpdateWatchSettings.get().as_str(),
+        params![threshold.abs()],
+    )?;
+    Ok(())
+}
+
+pub async fn show_watchlist(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let options = vec![
+        WatchMenu::Show,
+        WatchMenu::CheckAlerts,
+        WatchMenu::Add,
+        WatchMenu::Remove,
+        WatchMenu::Settings,
+        WatchMenu::Back,
+    ];
+    let ans = Select::new("Watchlist", options)
+        .with_render_config(render_config())
+        .prompt()?;
+
+    match ans {
+        WatchMenu::Show => {
+            let items = select_watchlist(conn)?;
+            if items.is_empty() {
+                Label::WatchlistEmpty.log();
+                return Ok(());
+            }
+            let rows: Vec<Vec<String>> = items.iter().map(watch_row).collect();
+            print_table(&WATCH_HEADERS, &rows);
+        }
+        WatchMenu::CheckAlerts => {
+            watchlist_alerts(conn)?;
+            deliver_webhooks(conn).await?;
+        }
+        WatchMenu::Add => add_watch(conn)?,
+        WatchMenu::Remove => remove_watch(conn)?,
+        WatchMenu::Settings => edit_watch_settings(conn)?,
+        WatchMenu::Back => {}
+    }
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use crate::label::Label;
+use crate::loads::get_client;
+use crate::menu::WebhookMenu;
+use crate::prompts::{prompt_choice, render_config};
+use crate::schema::{Choice, WatchAlerts, Webhook, WebhookEvent};
+use crate::sql::Sql;
+use crate::utils::print_table;
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use inquire::{MultiSelect, Password, Select, Text};
+use rusqlite::{params, Connection};
+use sha2::Sha256;
+use std::collections::HashSet;
+
+// An event is given up after this many failed deliveries...
+const MAX_ATTEMPTS: i32 = 9;
+// ...and retried after these delays (seconds) within one delivery run.
+const RETRY_DELAYS: [u64; 2] = [1, 4];
+
+const EVENT_NAMES: [&str; 5] = [
+    "reference.new",
+    "stage.completed",
+    "load.failed",
+    "status.changed",
+    "watchlist.alert",
+];
+
+pub enum Event<'a> {
+    NewReference { reference: &'a str },
+    StageCompleted { stage: &'a str },
+    LoadFailed { stage: &'a str, message: &'a str },
+    StatusChanged { from: &'a str, to: &'a str },
+    WatchlistAlert { alerts: &'a WatchAlerts<'a> },
+}
+
+impl<'a> Event<'a> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::NewReference { .. } => "reference.new",
+            Event::StageCompleted { .. } => "stage.completed",
+            Event::LoadFailed { .. } => "load.failed",
+            Event::StatusChanged { .. } => "status.changed",
+            Event::WatchlistAlert { .. } => "watchlist.alert",
+        }
+    }
+
+    fn data(&self) -> serde_json::Value {
+        match self {
+            Event::NewReference { reference } => serde_json::json!({ "reference": reference }),
+            Event::StageCompleted { stage } => serde_json::json!({ "stage": stage }),
+            Event::LoadFailed { stage, message } => {
+                serde_json::json!({ "stage": stage, "message": message })
+            }
+            Event::StatusChanged { from, to } => serde_json::json!({ "from": from, "to": to }),
+            Event::WatchlistAlert { alerts } => serde_json::json!(alerts),
+        }
+    }
+
+    // Queues the event for every subscribed webhook; `deliver_webhooks` sends it.
+    pub fn emit(&self, conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+        let payload = serde_json::json!({
+            "event": self.name(),
+            "occurred_at": Local::now().to_rfc3339(),
+            "data": self.data(),
+        });
+        conn.execute(
+            Sql::InsertWebhookEvent.get().as_str(),
+            params![self.name(), payload.to_string()],
+        )?;
+        Ok(())
+    }
+}
+
+pub fn select_webhooks(conn: &Connection) -> Result<Vec<Webhook>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectWebhooks.get().as_str())?;
+    let webhook_iter = stmt.query_map([], |row| {
+        Ok(Webhook {
+            id: row.get("id")?,
+            url: row.get("url")?,
+            secret: row.get("secret")?,
+            events: row.get("events")?,
+        })
+    })?;
+
+    let mut webhooks = Vec::new();
+    for webhook in webhook_iter {
+        webhooks.push(webhook?);
+    }
+    Ok(webhooks)
+}
+
+fn select_pending_events(
+    conn: &Connection,
+) -> Result<Vec<WebhookEvent>, Box<dyn std::error::Error>> {
+    let mut stmt = conn.prepare(Sql::SelectPendingWebhookEvents.get().as_str())?;
+    let event_iter = stmt.query_map([MAX_ATTEMPTS], |row| {
+        Ok(WebhookEvent {
+            id: row.get("id")?,
+            webhook_id: row.get("webhook_id")?,
+            event: row.get("event")?,
+            payload: row.get("payload")?,
+            url: row.get("url")?,
+            secret: row.get("secret")?,
+        })
+    })?;
+
+    let mut events = Vec::new();
+    for event in event_iter {
+        events.push(event?);
+    }
+    Ok(events)
+}
+
+// Hex HMAC-SHA256 of the raw request body, sent as "X-Fipe-Signature: sha256=<hex>".
+pub fn sign_payload(secret: &str, payload: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+async fn post_event(e: &WebhookEvent) -> Result<(), Box<dyn std::error::Error>> {
+    let mut request = get_client()
+        .post(&e.url)
+        .header("Content-Type", "application/json")
+        .header("X-Fipe-Event", &e.event)
+        .header("X-Fipe-Delivery", e.id.to_string());
+    if let Some(secret) = e.secret.as_deref().filter(|s| !s.is_empty()) {
+        request = request.header(
+            "X-Fipe-Signature",
+            format!("sha256={}", sign_payload(secret, &e.payload)),
+        );
+    }
+    let response = request.body(e.payload.clone()).send().await?;
+    if !response.status().is_success() {
+        return Err(format!("{} answered {}", e.url, response.status()).into());
+    }
+    Ok(())
+}
+
+// Sends queued events in order. A webhook that still fails after the retries is skipped for
+// the rest of the run, so its later events stay queued behind the failed one.
+pub async fn deliver_webhooks(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failing: HashSet<i32> = HashSet::new();
+    for e in select_pending_events(conn)? {
+        if failing.contains(&e.webhook_id) {
+            continue;
+        }
+        let mut attempts = 0;
+        let result = loop {
+            let result = post_event(&e).await;
+            if result.is_ok() || attempts == RETRY_DELAYS.len() {
+                break result;
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAYS[attempts])).await;
+            attempts += 1;
+        };
+        let attempts = attempts as i32 + 1;
+        match result {
+            Ok(()) => {
+                conn.execute(
+                    Sql::UpdateWebhookDelivered.get().as_str(),
+                    params![attempts, e.id],
+                )?;
+            }
+            Err(err) => {
+                let message = err.to_string();
+                conn.execute(
+                    Sql::UpdateWebhookFailed.get().as_str(),
+                    params![attempts, message, e.id],
+                )?;
+                (Label::WebhookError { message: &message }).log();
+                failing.insert(e.webhook_id);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn add_webhook(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let url = Text::new("Webhook URL:").prompt()?;
+    let secret = Password::new("Signing secret (empty for unsigned):")
+        .without_confirmation()
+        .prompt()?;
+    let events = MultiSelect::new("Events (none selected = all)", EVENT_NAMES.to_vec())
+        .with_render_config(render_config())
+        .prompt()?;
+    let events = if events.is_empty() || events.len() == EVENT_NAMES.len() {
+        "*".to_string()
+    } else {
+        events.join(",")
+    };
+
+    let url = url.trim();
+    let secret = Some(secret.trim()).filter(|s| !s.is_empty());
+    conn.execute(
+        Sql::InsertWebhook.get().as_str(),
+        params![url, secret, events],
+    )?;
+    (Label::WebhookAdded { url }).log();
+    Ok(())
+}
+
+pub fn configure_webhooks(conn: &Connection) -> Result<(), Box<dyn std::error::Error>> {
+    let options = vec![
+        WebhookMenu::List,
+        WebhookMenu::Add,
+        WebhookMenu::Remove,
+        WebhookMenu::Back,
+    ];
+    let ans = Select::new("Webhooks", options)
+        .with_render_config(render_config())
+        .prompt()?;
+
+    match ans {
+        WebhookMenu::List => {
+            let webhooks = select_webhooks(conn)?;
+            if webhooks.is_empty() {
+                Label::NoWebhooks.log();
+                return Ok(());
+            }
+            let rows: Vec<Vec<String>> = webhooks
+                .iter()
+                .map(|w| {
+                    vec![
+                        w.url.clone(),
+                        w.events.clone(),
+                        if w.secret.is_some() { "yes" } else { "no" }.to_string(),
+                    ]
+                })
+                .collect();
+            print_table(&["URL", "Events", "Signed"], &rows);
+        }
+        WebhookMenu::Add => add_webhook(conn)?,
+        WebhookMenu::Remove => {
+            let choices = select_webhooks(conn)?
+                .into_iter()
+                .map(|w| Choice {
+                    id: w.id,
+                    description: w.url,
+                })
+                .collect();
+            let Some(webhook) = prompt_choice("Remove", choices)? else {
+                Label::NoWebhooks.log();
+                return Ok(());
+            };
+            conn.execute(Sql::DeleteWebhook.get().as_str(), [webhook.id])?;
+            (Label::DeleteOk {
+                what: &webhook.description,
+            })
+            .log();
+        }
+        WebhookMenu::Back => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_payload_with_hmac_sha256() {
+        assert_eq!(
+            sign_payload("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}